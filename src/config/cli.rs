@@ -1,6 +1,10 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::config::schema::ConfigAsset;
+use crate::model::asset_name::AssetName;
+use crate::model::exe_name::ExeName;
 
 #[derive(Parser, Debug)]
 #[clap(author="Dmitrii Kovanikov <kovanikov@gmail.com>", version, about="A CLI tool to manage other CLI tools", long_about = None)]
@@ -9,15 +13,351 @@ pub struct Cli {
     #[clap(short, long, value_name = "FILE")]
     pub config: Option<PathBuf>,
 
+    #[clap(flatten)]
+    pub output: Output,
+
     #[clap(subcommand)]
     pub command: Command,
 }
 
+/// How to render progress and reports
+#[derive(Args, Clone, Copy, Debug)]
+pub struct Output {
+    /// Output format: progress for humans or newline-delimited JSON events
+    /// followed by a JSON report for scripts
+    #[clap(long = "output", global = true, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
+
+    /// When to use colors: 'auto' disables them if the output isn't a terminal
+    /// or the NO_COLOR environment variable is set
+    #[clap(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Don't print progress, and print the summary only if something failed
+    #[clap(short, long, global = true)]
+    pub quiet: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether colors should be forced on or off, or 'None' to let the
+    /// terminal decide. 'no_color' tells if NO_COLOR is set to a non-empty
+    /// value.
+    pub fn colors_enabled(self, no_color: bool) -> Option<bool> {
+        match self {
+            ColorChoice::Always => Some(true),
+            ColorChoice::Never => Some(false),
+            ColorChoice::Auto if no_color => Some(false),
+            ColorChoice::Auto => None,
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Sync all tools specified in configuration file
-    Sync,
+    Sync {
+        /// Reinstall tools even if they're already up to date
+        #[clap(long)]
+        force: bool,
+
+        /// Install exactly the assets from the lock file next to the config
+        #[clap(long)]
+        locked: bool,
+
+        /// Number of tools to install concurrently (default: number of CPUs)
+        #[clap(short, long, value_name = "N")]
+        jobs: Option<usize>,
+    },
+
+    /// Resolve all tools to concrete assets and write them to the lock file
+    /// next to the configuration file
+    Lock,
+
+    /// Compare installed tools with the latest available releases without
+    /// installing anything. Exits with a non-zero code if any tool is outdated
+    #[clap(alias = "outdated")]
+    Status,
+
+    /// Install a single tool without editing the configuration file
+    Install(InstallArgs),
+
+    /// Remove the executable of a tool from the store directory
+    Uninstall {
+        /// Name of the tool to remove
+        #[clap(value_name = "TOOL")]
+        tool: String,
+
+        /// Print what would be removed without removing anything
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Switch a tool to the previously installed version without downloading
+    /// anything
+    Rollback {
+        /// Name of the tool to roll back
+        #[clap(value_name = "TOOL")]
+        tool: String,
+    },
+
+    /// Remove tools installed by 'tool-sync' that are no longer in the
+    /// configuration file
+    Prune {
+        /// Print what would be removed without removing anything
+        #[clap(long)]
+        dry_run: bool,
+    },
 
     /// Generate a default .tools.toml file and prints it to std out
     DefaultConfig,
 }
+
+#[derive(Args, Debug)]
+pub struct InstallArgs {
+    /// Tool to install: either a name ('ripgrep') or a GitHub repository
+    /// ('BurntSushi/ripgrep'), optionally followed by a tag ('ripgrep@13.0.0')
+    #[clap(value_name = "TOOL")]
+    pub tool: String,
+
+    /// GitHub repository author
+    #[clap(long)]
+    pub owner: Option<String>,
+
+    /// GitHub repository name
+    #[clap(long)]
+    pub repo: Option<String>,
+
+    /// Executable name inside the .tar.gz or .zip archive. Repeat the flag to
+    /// install several executables from the same asset
+    #[clap(long)]
+    pub exe_name: Vec<String>,
+
+    /// Asset name to download on Linux
+    #[clap(long, value_name = "NAME")]
+    pub asset_name_linux: Option<String>,
+
+    /// Asset name to download on macOS
+    #[clap(long, value_name = "NAME")]
+    pub asset_name_macos: Option<String>,
+
+    /// Asset name to download on Windows
+    #[clap(long, value_name = "NAME")]
+    pub asset_name_windows: Option<String>,
+
+    /// Expected sha256 of the downloaded asset
+    #[clap(long)]
+    pub sha256: Option<String>,
+
+    /// How to unpack the asset: 'binary' for executables without archive or
+    /// an archive extension
+    #[clap(long)]
+    pub asset_type: Option<String>,
+
+    /// Glob pattern of the path to the executable inside the archive
+    /// (e.g. '*/bin/tool')
+    #[clap(long, value_name = "GLOB")]
+    pub exe_path: Option<String>,
+
+    /// Where releases are published: 'github', 'gitlab' or 'gitea'
+    #[clap(long)]
+    pub source: Option<String>,
+
+    /// Host of the instance of the source (e.g. 'gitlab.example.com')
+    #[clap(long)]
+    pub host: Option<String>,
+
+    /// Name of the environment variable with the token for the source
+    #[clap(long, value_name = "VAR")]
+    pub token_env: Option<String>,
+
+    /// Template of the URL to download the asset from instead of releases
+    /// (e.g. 'https://example.com/{version}/tool_{os}_{arch}.zip'). Requires
+    /// a tag
+    #[clap(long)]
+    pub url: Option<String>,
+
+    /// Consider pre-releases when looking for the latest release
+    #[clap(long)]
+    pub prerelease: bool,
+
+    /// Regular expression the tag of the latest release must match
+    /// (e.g. '^cli-v')
+    #[clap(long, value_name = "REGEX")]
+    pub tag_pattern: Option<String>,
+
+    /// Install the highest version matching the requirement (e.g. '^13')
+    /// instead of the latest release
+    #[clap(long, value_name = "REQ")]
+    pub version: Option<String>,
+
+    /// Add the tool to the configuration file after successful installation
+    #[clap(long)]
+    pub save: bool,
+}
+
+impl InstallArgs {
+    /// Get the tool name and build its configuration from CLI arguments.
+    /// Explicit flags take priority over the owner, repo and tag in 'tool'.
+    pub fn to_config_asset(&self) -> (String, ConfigAsset) {
+        let (name, tag) = match self.tool.split_once('@') {
+            None => (self.tool.as_str(), None),
+            Some((name, tag)) => (name, Some(tag.to_owned())),
+        };
+
+        let (tool_name, owner, repo) = match name.split_once('/') {
+            None => (name, None, None),
+            Some((owner, repo)) => (repo, Some(owner.to_owned()), Some(repo.to_owned())),
+        };
+
+        let config_asset = ConfigAsset {
+            owner: self.owner.clone().or(owner),
+            repo: self.repo.clone().or(repo),
+            exe_name: match self.exe_name.as_slice() {
+                [] => None,
+                [exe_name] => Some(ExeName::Single(exe_name.clone())),
+                exe_names => Some(ExeName::Multiple(exe_names.to_vec())),
+            },
+            asset_name: AssetName {
+                linux: self.asset_name_linux.clone(),
+                macos: self.asset_name_macos.clone(),
+                windows: self.asset_name_windows.clone(),
+            },
+            tag,
+            sha256: self.sha256.clone(),
+            asset_type: self.asset_type.clone(),
+            exe_path: self.exe_path.clone(),
+            completions: None,
+            man_pages: None,
+            api_url: None,
+            source: self.source.clone(),
+            host: self.host.clone(),
+            token_env: self.token_env.clone(),
+            url: self.url.clone(),
+            prerelease: self.prerelease.then_some(true),
+            tag_pattern: self.tag_pattern.clone(),
+            version: self.version.clone(),
+        };
+
+        (tool_name.to_owned(), config_asset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install_args(tool: &str) -> InstallArgs {
+        InstallArgs {
+            tool: tool.to_owned(),
+            owner: None,
+            repo: None,
+            exe_name: Vec::new(),
+            asset_name_linux: None,
+            asset_name_macos: None,
+            asset_name_windows: None,
+            sha256: None,
+            asset_type: None,
+            exe_path: None,
+            source: None,
+            host: None,
+            token_env: None,
+            url: None,
+            prerelease: false,
+            tag_pattern: None,
+            version: None,
+            save: false,
+        }
+    }
+
+    #[test]
+    fn install_by_name() {
+        assert_eq!(
+            install_args("ripgrep").to_config_asset(),
+            ("ripgrep".to_owned(), ConfigAsset::default())
+        );
+    }
+
+    #[test]
+    fn install_by_repo_with_tag() {
+        assert_eq!(
+            install_args("BurntSushi/ripgrep@13.0.0").to_config_asset(),
+            (
+                "ripgrep".to_owned(),
+                ConfigAsset {
+                    owner: Some("BurntSushi".to_owned()),
+                    repo: Some("ripgrep".to_owned()),
+                    tag: Some("13.0.0".to_owned()),
+                    ..ConfigAsset::default()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn flags_override_spec() {
+        let args = InstallArgs {
+            owner: Some("me".to_owned()),
+            exe_name: vec!["rg".to_owned()],
+            asset_name_linux: Some("musl".to_owned()),
+            ..install_args("BurntSushi/ripgrep")
+        };
+
+        assert_eq!(
+            args.to_config_asset(),
+            (
+                "ripgrep".to_owned(),
+                ConfigAsset {
+                    owner: Some("me".to_owned()),
+                    repo: Some("ripgrep".to_owned()),
+                    exe_name: Some(ExeName::from("rg")),
+                    asset_name: AssetName {
+                        linux: Some("musl".to_owned()),
+                        macos: None,
+                        windows: None,
+                    },
+                    ..ConfigAsset::default()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn repeated_exe_name_flags() {
+        let args = InstallArgs {
+            exe_name: vec!["kubectx".to_owned(), "kubens".to_owned()],
+            ..install_args("ahmetb/kubectx")
+        };
+
+        let (_, config_asset) = args.to_config_asset();
+
+        assert_eq!(
+            config_asset.exe_name,
+            Some(ExeName::Multiple(vec![
+                "kubectx".to_owned(),
+                "kubens".to_owned()
+            ]))
+        );
+    }
+
+    #[test]
+    fn color_choice() {
+        assert_eq!(ColorChoice::Always.colors_enabled(true), Some(true));
+        assert_eq!(ColorChoice::Never.colors_enabled(false), Some(false));
+        assert_eq!(ColorChoice::Auto.colors_enabled(true), Some(false));
+        assert_eq!(ColorChoice::Auto.colors_enabled(false), None);
+    }
+}
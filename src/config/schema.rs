@@ -4,22 +4,45 @@ use std::path::PathBuf;
 
 use crate::err;
 use crate::model::asset_name::AssetName;
+use crate::model::exe_name::ExeName;
+
+/// Default number of installed versions kept for each tool
+const DEFAULT_KEEP_VERSIONS: usize = 2;
 
 /// Stores global information about the tool installation process and detailed
 /// info about installing each particular tool.
 ///
 /// This data type is parsed from the TOML configuration file.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct Config {
     /// Directory to store all locally downloaded tools
     pub store_directory: String,
 
+    /// Directory to install shell completions shipped with tools
+    pub completions_directory: Option<String>,
+
+    /// Directory to install man pages shipped with tools
+    pub man_directory: Option<String>,
+
+    /// How many installed versions of each tool to keep, including the active
+    /// one. Defaults to 2, so the previous version is always available for
+    /// rollback
+    pub keep_versions: Option<usize>,
+
+    /// Base URL of the GitHub API, e.g. of a GitHub Enterprise Server instance.
+    /// Defaults to https://api.github.com
+    pub github_api_url: Option<String>,
+
+    /// Names of environment variables with tokens for each GitHub API host.
+    /// GITHUB_TOKEN is used for api.github.com if not specified
+    pub github_tokens: BTreeMap<String, String>,
+
     /// Info about each individual tool
     pub tools: BTreeMap<String, ConfigAsset>,
 }
 
 /// Additional details, telling how to download a tool
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct ConfigAsset {
     /// GitHub repository author
     pub owner: Option<String>,
@@ -27,9 +50,10 @@ pub struct ConfigAsset {
     /// GitHub repository name
     pub repo: Option<String>,
 
-    /// Executable name inside the .tar.gz or .zip archive
+    /// Executable name inside the .tar.gz or .zip archive: either a single
+    /// name, an array of names or a table with installed names
     /// Defaults to `repo` if not specified
-    pub exe_name: Option<String>,
+    pub exe_name: Option<ExeName>,
 
     /// Name of the specific asset to download
     pub asset_name: AssetName,
@@ -37,18 +61,74 @@ pub struct ConfigAsset {
     /// Release tag to download
     /// Defaults to the latest release
     pub tag: Option<String>,
+
+    /// Expected sha256 of the downloaded asset
+    pub sha256: Option<String>,
+
+    /// How to unpack the asset: 'binary' for executables without archive or
+    /// an archive extension. Detected automatically if not specified
+    pub asset_type: Option<String>,
+
+    /// Glob pattern of the path to the executable inside the archive
+    /// Searched recursively by `exe_name` if not specified
+    pub exe_path: Option<String>,
+
+    /// Glob patterns of shell completion files inside the archive
+    pub completions: Option<Vec<String>>,
+
+    /// Glob patterns of man pages inside the archive
+    pub man_pages: Option<Vec<String>>,
+
+    /// Base URL of the API to download this tool from. Overrides 'host' and
+    /// 'github_api_url' from the config
+    pub api_url: Option<String>,
+
+    /// Where releases are published: 'github', 'gitlab' or 'gitea'
+    /// Defaults to GitHub
+    pub source: Option<String>,
+
+    /// Host of the instance of 'source', e.g. 'gitlab.example.com'
+    /// Defaults to the public instance
+    pub host: Option<String>,
+
+    /// Name of the environment variable with the token for 'source'
+    /// Defaults to GITHUB_TOKEN for GitHub and GITLAB_TOKEN for GitLab
+    pub token_env: Option<String>,
+
+    /// Template of the URL to download the asset from directly, without
+    /// looking up releases, e.g. 'https://example.com/{version}/tool_{os}.zip'
+    /// Requires 'tag'
+    pub url: Option<String>,
+
+    /// Whether pre-releases count as the latest release
+    /// Defaults to false
+    pub prerelease: Option<bool>,
+
+    /// Regular expression the tag of the latest release must match,
+    /// e.g. '^cli-v' for monorepos
+    pub tag_pattern: Option<String>,
+
+    /// Requirement for the version to download instead of the latest one,
+    /// e.g. '^13' or '>=0.22, <0.24'. Can't be used together with 'tag'
+    pub version: Option<String>,
+}
+
+impl ConfigAsset {
+    /// Tag, version requirement or 'latest', to show before the release is
+    /// resolved
+    pub fn tag_label(&self) -> String {
+        self.tag
+            .clone()
+            .or_else(|| self.version.clone())
+            .unwrap_or_else(|| "latest".into())
+    }
 }
 
 impl Config {
     /// Shellexpands store directory, check whether it exists and exits with
     /// error if 'store_directory' doesn't exist
     pub fn ensure_store_directory(&self) -> PathBuf {
-        let expanded_store_directory = shellexpand::full(&self.store_directory);
-
-        let store_directory = match expanded_store_directory {
-            Err(e) => err::abort_with(&e.to_string()),
-            Ok(cow_path) => PathBuf::from(cow_path.into_owned()),
-        };
+        let store_directory = expand_path(&self.store_directory);
 
         let has_store_directory = store_directory.as_path().is_dir();
 
@@ -61,4 +141,27 @@ impl Config {
 
         store_directory
     }
+
+    /// Shellexpanded directory for shell completions, if configured
+    pub fn completions_directory(&self) -> Option<PathBuf> {
+        self.completions_directory.as_deref().map(expand_path)
+    }
+
+    /// Shellexpanded directory for man pages, if configured
+    pub fn man_directory(&self) -> Option<PathBuf> {
+        self.man_directory.as_deref().map(expand_path)
+    }
+
+    /// Number of versions to keep for each tool, at least the active one
+    pub fn keep_versions(&self) -> usize {
+        self.keep_versions.unwrap_or(DEFAULT_KEEP_VERSIONS).max(1)
+    }
+}
+
+/// Shellexpand the path or exit with error
+fn expand_path(path: &str) -> PathBuf {
+    match shellexpand::full(path) {
+        Err(e) => err::abort_with(&e.to_string()),
+        Ok(cow_path) => PathBuf::from(cow_path.into_owned()),
+    }
 }
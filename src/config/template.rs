@@ -1,4 +1,4 @@
-/// This file only holds the template that is used to generate a default .tools.toml.
+//! This file only holds the template that is used to generate a default .tools.toml.
 
 pub const CONFIG_TEMPLATE: &str = r##"# # tool-sync default configuration file
 # https://github.com/chshersh/tool-sync
@@ -8,6 +8,20 @@ pub const CONFIG_TEMPLATE: &str = r##"# # tool-sync default configuration file
 #
 # store_directory = "$HOME/.local/bin"
 #
+# Uncomment to install shell completions and man pages shipped with tools
+# completions_directory = "$HOME/.local/share/tool-sync/completions"
+# man_directory = "$HOME/.local/share/man"
+#
+# How many installed versions of each tool to keep for 'tool rollback'
+# keep_versions = 2
+#
+# Uncomment to download tools from a GitHub Enterprise Server instance and
+# read its token from the given environment variable
+# github_api_url = "https://github.example.com/api/v3"
+#
+# [github_tokens]
+# "github.example.com" = "EXAMPLE_GITHUB_TOKEN"
+#
 # tool-sync provides native support for some of the tools without the need to configure them
 # Uncomment the tools you want to have them
 #
@@ -22,10 +36,29 @@ pub const CONFIG_TEMPLATE: &str = r##"# # tool-sync default configuration file
 #        repo      = "ripgrep"
 #        exe_name  = "rg"
 #
+#        # Uncomment to download releases from GitLab or Gitea (e.g.
+#        # Codeberg) instead of GitHub
+#        # source    = "gitlab"
+#        # host      = "gitlab.example.com"
+#        # token_env = "GITLAB_EXAMPLE_TOKEN"
+#
+#        # Uncomment to download the asset from a URL template instead of
+#        # releases; requires 'tag'
+#        # url       = "https://example.com/{version}/{exe}_{os}_{arch}.zip"
+#
 #        # Uncomment to download a specific version or tag.
 #        # Without this tag latest will be used
 #        # tag       = "13.0.0"
 #
+#        # Or uncomment to download the highest version matching a
+#        # requirement
+#        # version   = "^13"
+#
+#        # Uncomment to consider pre-releases or only tags matching a
+#        # regular expression when looking for the latest release
+#        # prerelease  = true
+#        # tag_pattern = "^v13\\."
+#
 #
 # Asset name to download on linux OSes
 # asset_name.linux = "x86_64-unknown-linux-musl"
@@ -1,16 +1,19 @@
 use std::collections::BTreeMap;
-use std::fs;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
 use std::path::PathBuf;
 use toml::{map::Map, Value};
 
 use crate::config::schema::{Config, ConfigAsset};
 use crate::model::asset_name::AssetName;
+use crate::model::exe_name::ExeName;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum TomlError {
     IO(String),
     Parse(toml::de::Error),
     Decode,
+    EmptyExeName(String),
 }
 
 impl TomlError {
@@ -19,6 +22,10 @@ impl TomlError {
             TomlError::IO(e) => format!("[IO Error] {}", e),
             TomlError::Parse(e) => format!("[Parsing Error] {}", e),
             TomlError::Decode => "[Decode Error]".to_string(),
+            TomlError::EmptyExeName(tool_name) => format!(
+                "[Decode Error] 'exe_name' of '{}' doesn't list any executables",
+                tool_name
+            ),
         }
     }
 }
@@ -29,6 +36,120 @@ pub fn parse_file(config_path: &PathBuf) -> Result<Config, TomlError> {
     parse_string(&contents)
 }
 
+/// Append a new tool entry to the end of the configuration file. The file is
+/// appended to instead of rewritten to keep existing comments and formatting.
+pub fn append_tool(
+    config_path: &PathBuf,
+    tool_name: &str,
+    config_asset: &ConfigAsset,
+) -> Result<(), TomlError> {
+    let mut config_file = OpenOptions::new()
+        .append(true)
+        .open(config_path)
+        .map_err(|e| TomlError::IO(format!("{}", e)))?;
+
+    write!(
+        config_file,
+        "\n{}",
+        encode_config_asset(tool_name, config_asset)
+    )
+    .map_err(|e| TomlError::IO(format!("{}", e)))
+}
+
+fn encode_config_asset(tool_name: &str, config_asset: &ConfigAsset) -> String {
+    let mut entry = format!("[{}]\n", encode_key(tool_name));
+
+    let encode_str = |value: &Option<String>| value.as_deref().map(|v| Value::from(v).to_string());
+    let encode_list = |value: &Option<Vec<String>>| {
+        value
+            .as_ref()
+            .map(|values| Value::from(values.clone()).to_string())
+    };
+
+    let fields = [
+        ("owner", encode_str(&config_asset.owner)),
+        ("repo", encode_str(&config_asset.repo)),
+        (
+            "exe_name",
+            config_asset.exe_name.as_ref().map(encode_exe_name),
+        ),
+        (
+            "asset_name.linux",
+            encode_str(&config_asset.asset_name.linux),
+        ),
+        (
+            "asset_name.macos",
+            encode_str(&config_asset.asset_name.macos),
+        ),
+        (
+            "asset_name.windows",
+            encode_str(&config_asset.asset_name.windows),
+        ),
+        ("tag", encode_str(&config_asset.tag)),
+        ("sha256", encode_str(&config_asset.sha256)),
+        ("asset_type", encode_str(&config_asset.asset_type)),
+        ("exe_path", encode_str(&config_asset.exe_path)),
+        ("completions", encode_list(&config_asset.completions)),
+        ("man_pages", encode_list(&config_asset.man_pages)),
+        ("api_url", encode_str(&config_asset.api_url)),
+        ("source", encode_str(&config_asset.source)),
+        ("host", encode_str(&config_asset.host)),
+        ("token_env", encode_str(&config_asset.token_env)),
+        ("url", encode_str(&config_asset.url)),
+        (
+            "prerelease",
+            config_asset
+                .prerelease
+                .map(|prerelease| Value::from(prerelease).to_string()),
+        ),
+        ("tag_pattern", encode_str(&config_asset.tag_pattern)),
+        ("version", encode_str(&config_asset.version)),
+    ];
+
+    for (key, value) in fields {
+        if let Some(value) = value {
+            entry.push_str(&format!("{} = {}\n", key, value));
+        }
+    }
+
+    entry
+}
+
+fn encode_exe_name(exe_name: &ExeName) -> String {
+    match exe_name {
+        ExeName::Single(name) => Value::from(name.as_str()).to_string(),
+        ExeName::Multiple(names) => Value::from(names.clone()).to_string(),
+        ExeName::Renamed(names) => {
+            let pairs: Vec<String> = names
+                .iter()
+                .map(|(name, installed_name)| {
+                    format!(
+                        "{} = {}",
+                        encode_key(name),
+                        Value::from(installed_name.as_str())
+                    )
+                })
+                .collect();
+
+            format!("{{ {} }}", pairs.join(", "))
+        }
+    }
+}
+
+/// Quote the key only when it can't be written as a TOML bare key
+fn encode_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if is_bare {
+        key.to_owned()
+    } else {
+        Value::from(key).to_string()
+    }
+}
+
 fn parse_string(contents: &str) -> Result<Config, TomlError> {
     contents
         .parse::<Value>()
@@ -37,32 +158,101 @@ fn parse_string(contents: &str) -> Result<Config, TomlError> {
             None => Err(TomlError::Decode),
             Some(config) => Ok(config),
         })
+        .and_then(|config| {
+            check_exe_names(&config)?;
+            Ok(config)
+        })
+}
+
+/// Tools with 'exe_name = []' or 'exe_name = {}' would install nothing
+fn check_exe_names(config: &Config) -> Result<(), TomlError> {
+    match config.tools.iter().find(|(_, config_asset)| {
+        config_asset
+            .exe_name
+            .as_ref()
+            .is_some_and(|exe_name| exe_name.executables().is_empty())
+    }) {
+        Some((tool_name, _)) => Err(TomlError::EmptyExeName(tool_name.clone())),
+        None => Ok(()),
+    }
 }
 
 fn decode_config(toml: Value) -> Option<Config> {
     let str_store_directory = toml.get("store_directory")?.as_str()?;
     let store_directory = String::from(str_store_directory);
 
+    let completions_directory = toml
+        .get("completions_directory")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    let man_directory = toml
+        .get("man_directory")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    let keep_versions = toml
+        .get("keep_versions")
+        .and_then(|v| v.as_integer())
+        .and_then(|n| usize::try_from(n).ok());
+    let github_api_url = toml
+        .get("github_api_url")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    let github_tokens = toml
+        .get("github_tokens")
+        .and_then(|v| v.as_table())
+        .map(decode_github_tokens)
+        .unwrap_or_default();
+
     let mut tools = BTreeMap::new();
 
     for (key, val) in toml.as_table()?.iter() {
         if let Value::Table(table) = val {
-            tools.insert(key.clone(), decode_config_asset(table));
+            // the only top-level table that isn't a tool
+            if key != "github_tokens" {
+                tools.insert(key.clone(), decode_config_asset(table));
+            }
         }
     }
 
     Some(Config {
         store_directory,
+        completions_directory,
+        man_directory,
+        keep_versions,
+        github_api_url,
+        github_tokens,
         tools,
     })
 }
 
+/// Environment variable names of tokens for each host, ignoring non-string
+/// values
+fn decode_github_tokens(table: &Map<String, Value>) -> BTreeMap<String, String> {
+    table
+        .iter()
+        .filter_map(|(host, env_var)| Some((host.clone(), env_var.as_str()?.to_owned())))
+        .collect()
+}
+
 fn decode_config_asset(table: &Map<String, Value>) -> ConfigAsset {
     let owner = str_by_key(table, "owner");
     let repo = str_by_key(table, "repo");
-    let exe_name = str_by_key(table, "exe_name");
+    let exe_name = decode_exe_name(table);
     let asset_name = decode_asset_name(table);
     let tag = str_by_key(table, "tag");
+    let sha256 = str_by_key(table, "sha256");
+    let asset_type = str_by_key(table, "asset_type");
+    let exe_path = str_by_key(table, "exe_path");
+    let completions = str_list_by_key(table, "completions");
+    let man_pages = str_list_by_key(table, "man_pages");
+    let api_url = str_by_key(table, "api_url");
+    let source = str_by_key(table, "source");
+    let host = str_by_key(table, "host");
+    let token_env = str_by_key(table, "token_env");
+    let url = str_by_key(table, "url");
+    let prerelease = table.get("prerelease").and_then(|v| v.as_bool());
+    let tag_pattern = str_by_key(table, "tag_pattern");
+    let version = str_by_key(table, "version");
 
     ConfigAsset {
         owner,
@@ -70,6 +260,19 @@ fn decode_config_asset(table: &Map<String, Value>) -> ConfigAsset {
         exe_name,
         asset_name,
         tag,
+        sha256,
+        asset_type,
+        exe_path,
+        completions,
+        man_pages,
+        api_url,
+        source,
+        host,
+        token_env,
+        url,
+        prerelease,
+        tag_pattern,
+        version,
     }
 }
 
@@ -95,10 +298,45 @@ fn decode_asset_name(table: &Map<String, Value>) -> AssetName {
     }
 }
 
+/// Executable names: either a string, an array of strings or a table mapping
+/// names inside the asset to installed names
+fn decode_exe_name(table: &Map<String, Value>) -> Option<ExeName> {
+    match table.get("exe_name")? {
+        Value::String(name) => Some(ExeName::Single(name.clone())),
+        Value::Array(names) => names
+            .iter()
+            .map(|name| name.as_str().map(String::from))
+            .collect::<Option<Vec<String>>>()
+            .map(ExeName::Multiple),
+        Value::Table(names) => names
+            .iter()
+            .map(|(name, installed_name)| {
+                installed_name
+                    .as_str()
+                    .map(|installed_name| (name.clone(), installed_name.to_owned()))
+            })
+            .collect::<Option<BTreeMap<String, String>>>()
+            .map(ExeName::Renamed),
+        _ => None,
+    }
+}
+
 fn str_by_key(table: &Map<String, Value>, key: &str) -> Option<String> {
     table.get(key).and_then(|v| v.as_str()).map(String::from)
 }
 
+/// Either a single string or an array of strings
+fn str_list_by_key(table: &Map<String, Value>, key: &str) -> Option<Vec<String>> {
+    match table.get(key)? {
+        Value::String(value) => Some(vec![value.clone()]),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| value.as_str().map(String::from))
+            .collect(),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,6 +373,7 @@ mod tests {
         let cfg = Config {
             store_directory: String::from("pancake"),
             tools: BTreeMap::new(),
+            ..Config::default()
         };
 
         assert_eq!(res, Ok(cfg));
@@ -164,8 +403,10 @@ mod tests {
                         windows: None,
                     },
                     tag: None,
+                    ..ConfigAsset::default()
                 },
             )]),
+            ..Config::default()
         };
 
         assert_eq!(res, Ok(cfg));
@@ -197,6 +438,7 @@ mod tests {
                             windows: None,
                         },
                         tag: None,
+                        ..ConfigAsset::default()
                     },
                 ),
                 (
@@ -211,9 +453,11 @@ mod tests {
                             windows: None,
                         },
                         tag: None,
+                        ..ConfigAsset::default()
                     },
                 ),
             ]),
+            ..Config::default()
         };
 
         assert_eq!(res, Ok(cfg));
@@ -245,8 +489,10 @@ mod tests {
                         windows: None,
                     },
                     tag: None,
+                    ..ConfigAsset::default()
                 },
             )]),
+            ..Config::default()
         };
 
         assert_eq!(res, Ok(cfg));
@@ -276,15 +522,262 @@ mod tests {
                 ConfigAsset {
                     owner: Some("me".to_owned()),
                     repo: Some("some_repo".to_owned()),
-                    exe_name: Some("rg".to_owned()),
+                    exe_name: Some(ExeName::from("rg")),
                     asset_name: AssetName {
                         linux: Some("R2D2".to_owned()),
                         macos: Some("C3-PO".to_owned()),
                         windows: Some("IG-88".to_owned()),
                     },
                     tag: Some("4.2.0".to_owned()),
+                    ..ConfigAsset::default()
+                },
+            )]),
+            ..Config::default()
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn sha256_key() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            sha256 = "abcdef"
+        "#;
+
+        let res = parse_string(toml).map(|cfg| cfg.tools["ripgrep"].sha256.clone());
+
+        assert_eq!(res, Ok(Some("abcdef".to_owned())));
+    }
+
+    #[test]
+    fn asset_type_key() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            asset_type = "binary"
+        "#;
+
+        let res = parse_string(toml).map(|cfg| cfg.tools["ripgrep"].asset_type.clone());
+
+        assert_eq!(res, Ok(Some("binary".to_owned())));
+    }
+
+    #[test]
+    fn exe_path_key() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            exe_path = "*/bin/rg"
+        "#;
+
+        let res = parse_string(toml).map(|cfg| cfg.tools["ripgrep"].exe_path.clone());
+
+        assert_eq!(res, Ok(Some("*/bin/rg".to_owned())));
+    }
+
+    #[test]
+    fn encoded_tool_is_parsed_back() {
+        let config_asset = ConfigAsset {
+            owner: Some("BurntSushi".to_owned()),
+            repo: Some("ripgrep".to_owned()),
+            exe_name: Some(ExeName::from("rg")),
+            asset_name: AssetName {
+                linux: Some("unknown-linux-musl".to_owned()),
+                macos: None,
+                windows: Some("x86_64-pc-windows-msvc".to_owned()),
+            },
+            tag: Some("13.0.0".to_owned()),
+            ..ConfigAsset::default()
+        };
+
+        let toml = format!(
+            "store_directory = \"pancake\"\n\n{}",
+            encode_config_asset("my ripgrep", &config_asset)
+        );
+
+        let res = parse_string(&toml);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            tools: BTreeMap::from([("my ripgrep".to_owned(), config_asset)]),
+            ..Config::default()
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn several_exe_names() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [kubectx]
+            exe_name = ["kubectx", "kubens"]
+
+            [fzf]
+            exe_name = { fzf = "fzf", "fzf-tmux" = "ftmux" }
+        "#;
+
+        let res = parse_string(toml).map(|cfg| {
+            cfg.tools
+                .into_iter()
+                .map(|(tool_name, config_asset)| (tool_name, config_asset.exe_name))
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(
+            res,
+            Ok(vec![
+                (
+                    "fzf".to_owned(),
+                    Some(ExeName::Renamed(BTreeMap::from([
+                        ("fzf".to_owned(), "fzf".to_owned()),
+                        ("fzf-tmux".to_owned(), "ftmux".to_owned()),
+                    ])))
+                ),
+                (
+                    "kubectx".to_owned(),
+                    Some(ExeName::Multiple(vec![
+                        "kubectx".to_owned(),
+                        "kubens".to_owned()
+                    ]))
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn empty_exe_names() {
+        for exe_name in ["[]", "{}"] {
+            let toml = format!(
+                r#"
+                store_directory = "pancake"
+
+                [kubectx]
+                exe_name = {}
+                "#,
+                exe_name
+            );
+
+            assert_eq!(
+                parse_string(&toml),
+                Err(TomlError::EmptyExeName("kubectx".to_owned()))
+            );
+        }
+    }
+
+    #[test]
+    fn encoded_exe_names_are_parsed_back() {
+        let exe_names = [
+            ExeName::Multiple(vec!["kubectx".to_owned(), "kubens".to_owned()]),
+            ExeName::Renamed(BTreeMap::from([
+                ("fzf".to_owned(), "fzf".to_owned()),
+                ("fzf-tmux".to_owned(), "ftmux".to_owned()),
+            ])),
+        ];
+
+        for exe_name in exe_names {
+            let config_asset = ConfigAsset {
+                exe_name: Some(exe_name),
+                ..ConfigAsset::default()
+            };
+
+            let toml = format!(
+                "store_directory = \"pancake\"\n\n{}",
+                encode_config_asset("tool", &config_asset)
+            );
+
+            let res = parse_string(&toml).map(|cfg| cfg.tools.get("tool").cloned());
+
+            assert_eq!(res, Ok(Some(config_asset)));
+        }
+    }
+
+    #[test]
+    fn completions_and_man_pages() {
+        let toml = r#"
+            store_directory = "pancake"
+            completions_directory = "~/.local/share/completions"
+            man_directory = "~/.local/share/man"
+
+            [ripgrep]
+            completions = "complete/*"
+            man_pages = ["doc/*.1", "doc/*.5"]
+        "#;
+
+        let res = parse_string(toml);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            completions_directory: Some(String::from("~/.local/share/completions")),
+            man_directory: Some(String::from("~/.local/share/man")),
+            tools: BTreeMap::from([(
+                "ripgrep".to_owned(),
+                ConfigAsset {
+                    completions: Some(vec!["complete/*".to_owned()]),
+                    man_pages: Some(vec!["doc/*.1".to_owned(), "doc/*.5".to_owned()]),
+                    ..ConfigAsset::default()
+                },
+            )]),
+            ..Config::default()
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn keep_versions() {
+        let toml = r#"
+            store_directory = "pancake"
+            keep_versions = 3
+        "#;
+
+        let res = parse_string(toml);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            keep_versions: Some(3),
+            ..Config::default()
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn github_enterprise() {
+        let toml = r#"
+            store_directory = "pancake"
+            github_api_url = "https://github.example.com/api/v3"
+
+            [github_tokens]
+            "github.example.com" = "EXAMPLE_TOKEN"
+
+            [ripgrep]
+            api_url = "https://api.github.com"
+        "#;
+
+        let res = parse_string(toml);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            github_api_url: Some(String::from("https://github.example.com/api/v3")),
+            github_tokens: BTreeMap::from([(
+                "github.example.com".to_owned(),
+                "EXAMPLE_TOKEN".to_owned(),
+            )]),
+            tools: BTreeMap::from([(
+                "ripgrep".to_owned(),
+                ConfigAsset {
+                    api_url: Some(String::from("https://api.github.com")),
+                    ..ConfigAsset::default()
                 },
             )]),
+            ..Config::default()
         };
 
         assert_eq!(res, Ok(cfg));
@@ -4,36 +4,112 @@ mod model;
 mod sync;
 
 use clap::Parser;
+use std::env;
 use std::path::PathBuf;
+use std::process;
 
-use crate::config::cli::{Cli, Command};
+use crate::config::cli::{Cli, ColorChoice, Command, InstallArgs, Output};
+use crate::config::schema::Config;
 use crate::config::template;
 use crate::config::toml;
-use crate::sync::sync;
+use crate::sync::lock::{lock, require_lock_file};
+use crate::sync::outcome::SYNC_FAILURE_EXIT_CODE;
+use crate::sync::remove::{prune, uninstall};
+use crate::sync::status::status;
+use crate::sync::versions::rollback;
+use crate::sync::{install, sync};
 
 const DEFAULT_CONFIG_PATH: &str = ".tool.toml";
 
 pub fn run() {
     let cli = Cli::parse();
+    configure_colors(cli.output.color);
+
     let config_path = resolve_config_path(cli.config.clone());
 
     match cli.command {
-        Command::Sync => match toml::parse_file(&config_path) {
-            Err(e) => {
-                err::abort_with(&format!(
-                    "Error parsing configuration at path {}: {}",
-                    config_path.display(),
-                    e.display()
-                ));
-            }
-            Ok(tool) => {
-                sync(tool);
-            }
-        },
+        Command::Sync {
+            force,
+            locked,
+            jobs,
+        } => {
+            let lock_file = locked.then(|| require_lock_file(&config_path));
+            sync(
+                parse_config(&config_path),
+                cli.output,
+                force,
+                lock_file,
+                jobs,
+            )
+        }
+        Command::Lock => lock(parse_config(&config_path), cli.output, &config_path),
+        Command::Status => status(parse_config(&config_path), cli.output),
+        Command::Install(install_args) => install_tool(&config_path, cli.output, install_args),
+        Command::Uninstall { tool, dry_run } => {
+            uninstall(parse_config(&config_path), &tool, dry_run)
+        }
+        Command::Rollback { tool } => rollback(parse_config(&config_path), &tool),
+        Command::Prune { dry_run } => prune(parse_config(&config_path), dry_run),
         Command::DefaultConfig => generate_config(),
     }
 }
 
+/// Install a single tool from CLI arguments and optionally save it to config
+fn install_tool(config_path: &PathBuf, output: Output, install_args: InstallArgs) {
+    let config = parse_config(config_path);
+    let (tool_name, config_asset) = install_args.to_config_asset();
+
+    if install_args.save && config.tools.contains_key(&tool_name) {
+        err::abort_with(&format!(
+            "Tool '{}' is already specified in the configuration file at path {}",
+            tool_name,
+            config_path.display()
+        ));
+    }
+
+    let outcome = install(&config, output, &tool_name, &config_asset);
+
+    if install_args.save && outcome.is_installed() {
+        if let Err(e) = toml::append_tool(config_path, &tool_name, &config_asset) {
+            err::abort_with(&format!(
+                "Error saving tool '{}' to configuration at path {}: {}",
+                tool_name,
+                config_path.display(),
+                e.display()
+            ));
+        }
+    }
+
+    if outcome.is_failure() {
+        process::exit(SYNC_FAILURE_EXIT_CODE);
+    }
+}
+
+/// Force colors on or off for both stdout and stderr, unless the terminal
+/// should decide
+fn configure_colors(color: ColorChoice) {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+
+    if let Some(enabled) = color.colors_enabled(no_color) {
+        console::set_colors_enabled(enabled);
+        console::set_colors_enabled_stderr(enabled);
+    }
+}
+
+/// Parse the configuration file or exit with error
+fn parse_config(config_path: &PathBuf) -> Config {
+    match toml::parse_file(config_path) {
+        Err(e) => {
+            err::abort_with(&format!(
+                "Error parsing configuration at path {}: {}",
+                config_path.display(),
+                e.display()
+            ));
+        }
+        Ok(config) => config,
+    }
+}
+
 fn resolve_config_path(config_path: Option<PathBuf>) -> PathBuf {
     match config_path {
         Some(path) => path,
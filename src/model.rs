@@ -1,3 +1,6 @@
 pub mod asset_name;
+pub mod exe_name;
 pub mod release;
 pub mod tool;
+pub mod url_template;
+pub mod version;
@@ -1,7 +1,7 @@
 use std::env;
 
 /// Part of the name for each OS to identify proper asset
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct AssetName {
     pub linux: Option<String>,
     pub macos: Option<String>,
@@ -16,7 +16,13 @@ impl AssetName {
     /// compiled. The function relies on the assumption that a user will run e.g.
     /// the macOS executable on macOS
     pub fn get_name_by_os(&self) -> Option<&String> {
-        match env::consts::OS {
+        self.get_name(env::consts::OS)
+    }
+
+    /// Name of the asset for the given OS (as in 'std::env::consts::OS').
+    /// Systems other than Windows and macOS use Linux assets.
+    pub fn get_name(&self, os: &str) -> Option<&String> {
+        match os {
             "windows" => self.windows.as_ref(),
             "macos" => self.macos.as_ref(),
             _ => self.linux.as_ref(),
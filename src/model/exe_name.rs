@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+/// Executables to install from a single asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExeName {
+    /// A single executable, e.g. exe_name = "rg"
+    Single(String),
+
+    /// Several executables installed under the same names,
+    /// e.g. exe_name = ["kubectx", "kubens"]
+    Multiple(Vec<String>),
+
+    /// Executables inside the asset mapped to their installed names,
+    /// e.g. exe_name = { "fzf" = "fzf", "fzf-tmux" = "ftmux" }
+    Renamed(BTreeMap<String, String>),
+}
+
+impl ExeName {
+    /// Pairs of the executable name inside the asset and its installed name
+    pub fn executables(&self) -> Vec<(&str, &str)> {
+        match self {
+            ExeName::Single(name) => vec![(name, name)],
+            ExeName::Multiple(names) => names
+                .iter()
+                .map(|name| (name.as_str(), name.as_str()))
+                .collect(),
+            ExeName::Renamed(names) => names
+                .iter()
+                .map(|(name, installed_name)| (name.as_str(), installed_name.as_str()))
+                .collect(),
+        }
+    }
+
+    /// Installed names of all executables
+    pub fn installed_names(&self) -> Vec<&str> {
+        self.executables()
+            .into_iter()
+            .map(|(_, installed_name)| installed_name)
+            .collect()
+    }
+}
+
+impl From<&str> for ExeName {
+    fn from(name: &str) -> Self {
+        ExeName::Single(name.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn executables() {
+        assert_eq!(ExeName::from("rg").executables(), vec![("rg", "rg")]);
+
+        assert_eq!(
+            ExeName::Multiple(vec!["kubectx".to_owned(), "kubens".to_owned()]).executables(),
+            vec![("kubectx", "kubectx"), ("kubens", "kubens")]
+        );
+
+        let renamed = ExeName::Renamed(BTreeMap::from([
+            ("fzf".to_owned(), "fzf".to_owned()),
+            ("fzf-tmux".to_owned(), "ftmux".to_owned()),
+        ]));
+
+        assert_eq!(
+            renamed.executables(),
+            vec![("fzf", "fzf"), ("fzf-tmux", "ftmux")]
+        );
+        assert_eq!(renamed.installed_names(), vec!["fzf", "ftmux"]);
+    }
+}
@@ -1,14 +1,169 @@
+use regex::Regex;
+use semver::VersionReq;
 use serde::Deserialize;
 
+use crate::model::version::{matches_requirement, tag_version};
+
 #[derive(Deserialize, Debug)]
 pub struct Release {
     pub tag_name: String,
     pub assets: Vec<Asset>,
+
+    /// Marked as a pre-release by its authors
+    #[serde(default)]
+    pub prerelease: bool,
+
+    /// Drafts are listed only for maintainers and can't be installed
+    #[serde(default)]
+    pub draft: bool,
+
+    /// Time of publishing in ISO 8601, e.g. '2022-09-01T12:00:00Z'
+    #[serde(default)]
+    pub published_at: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct Asset {
     pub id: u32,
     pub name: String,
-    pub size: u64,
+
+    /// Size in bytes, unknown for GitLab release links
+    pub size: Option<u64>,
+
+    pub browser_download_url: String,
+}
+
+/// Pick the newest release with the tag matching the pattern and the
+/// version matching the requirement. Pre-releases, either marked so or with
+/// a pre-release version like '1.0.0-rc.1', are considered only if allowed.
+///
+/// Releases are compared by semantic versions of their tags. If none of the
+/// matching tags is a version, the most recently published release wins.
+pub fn latest_release(
+    releases: Vec<Release>,
+    prerelease: bool,
+    tag_pattern: Option<&Regex>,
+    requirement: Option<&VersionReq>,
+) -> Option<Release> {
+    let candidates = releases
+        .into_iter()
+        .map(|release| (tag_version(&release.tag_name), release))
+        .filter(|(version, release)| {
+            let is_prerelease = release.prerelease
+                || version
+                    .as_ref()
+                    .is_some_and(|version| !version.pre.is_empty());
+
+            let matches_version = match (requirement, version) {
+                (None, _) => true,
+                (Some(requirement), Some(version)) => {
+                    matches_requirement(requirement, version, prerelease)
+                }
+                (Some(_), None) => false,
+            };
+
+            !release.draft
+                && (prerelease || !is_prerelease)
+                && tag_pattern.map_or(true, |pattern| pattern.is_match(&release.tag_name))
+                && matches_version
+        });
+
+    let (versioned, unversioned): (Vec<_>, Vec<_>) =
+        candidates.partition(|(version, _)| version.is_some());
+
+    if versioned.is_empty() {
+        unversioned
+            .into_iter()
+            .map(|(_, release)| release)
+            .max_by(|a, b| a.published_at.cmp(&b.published_at))
+    } else {
+        versioned
+            .into_iter()
+            .max_by(|(version_a, a), (version_b, b)| {
+                version_a
+                    .cmp(version_b)
+                    .then_with(|| a.published_at.cmp(&b.published_at))
+            })
+            .map(|(_, release)| release)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag_name: &str, prerelease: bool, published_at: &str) -> Release {
+        Release {
+            tag_name: tag_name.to_owned(),
+            assets: Vec::new(),
+            prerelease,
+            draft: false,
+            published_at: Some(published_at.to_owned()),
+        }
+    }
+
+    fn monorepo_releases() -> Vec<Release> {
+        vec![
+            release("sdk-v1.1.0", false, "2022-09-05T00:00:00Z"),
+            release("cli-v2.4.0-rc.1", false, "2022-09-04T00:00:00Z"),
+            release("cli-v2.4.0-beta", true, "2022-09-03T00:00:00Z"),
+            release("cli-v1.9.1", false, "2022-09-02T00:00:00Z"),
+            release("cli-v2.3.0", false, "2022-09-01T00:00:00Z"),
+        ]
+    }
+
+    fn latest_tag(prerelease: bool, tag_pattern: Option<&str>) -> Option<String> {
+        let tag_pattern = tag_pattern.map(|pattern| Regex::new(pattern).unwrap());
+
+        latest_release(monorepo_releases(), prerelease, tag_pattern.as_ref(), None)
+            .map(|release| release.tag_name)
+    }
+
+    #[test]
+    fn latest_by_version() {
+        assert_eq!(
+            latest_tag(false, Some("^cli-")),
+            Some("cli-v2.3.0".to_owned())
+        );
+        assert_eq!(
+            latest_tag(true, Some("^cli-")),
+            Some("cli-v2.4.0-rc.1".to_owned())
+        );
+        assert_eq!(
+            latest_tag(false, Some(r"^cli-v1\.")),
+            Some("cli-v1.9.1".to_owned())
+        );
+        assert_eq!(latest_tag(false, Some("^tui-")), None);
+    }
+
+    #[test]
+    fn latest_by_requirement() {
+        let releases = vec![
+            release("14.0.0", false, "2022-09-04T00:00:00Z"),
+            release("v13.1.0", false, "2022-09-03T00:00:00Z"),
+            release("release-13.2.1", false, "2022-09-02T00:00:00Z"),
+            release("13.0.0", false, "2022-09-01T00:00:00Z"),
+            release("nightly", false, "2022-09-05T00:00:00Z"),
+        ];
+        let requirement = VersionReq::parse("^13").unwrap();
+
+        assert_eq!(
+            latest_release(releases, false, None, Some(&requirement))
+                .map(|release| release.tag_name),
+            Some("release-13.2.1".to_owned())
+        );
+    }
+
+    #[test]
+    fn latest_by_publish_date() {
+        let releases = vec![
+            release("nightly-b", false, "2022-09-01T00:00:00Z"),
+            release("nightly-a", false, "2022-09-02T00:00:00Z"),
+        ];
+
+        assert_eq!(
+            latest_release(releases, false, None, None).map(|release| release.tag_name),
+            Some("nightly-a".to_owned())
+        );
+    }
 }
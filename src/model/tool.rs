@@ -1,5 +1,9 @@
+use semver::VersionReq;
+
 use crate::model::asset_name::AssetName;
+use crate::model::exe_name::ExeName;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Tool {
     Known(ToolInfo),
@@ -13,6 +17,21 @@ pub enum ToolError {
 
     /// Not enough configuration to install the tool
     Invalid,
+
+    /// The 'source' key has an unsupported value
+    UnknownSource { source: String },
+
+    /// The 'url' key without 'tag': the version to download is unknown
+    UrlWithoutTag,
+
+    /// The 'tag_pattern' key isn't a valid regular expression
+    InvalidTagPattern { pattern: String, error: String },
+
+    /// Both 'tag' and 'version' keys: only one of them can be used
+    TagAndVersion,
+
+    /// The 'version' key isn't a valid version requirement
+    InvalidVersion { version: String, error: String },
 }
 
 impl ToolError {
@@ -22,6 +41,27 @@ impl ToolError {
                 format!("[suggestion] Perhaps you meant: '{}'?", perhaps)
             }
             ToolError::Invalid => "[error] Not detailed enough configuration)".to_string(),
+            ToolError::UnknownSource { source } => {
+                format!(
+                    "[error] Unknown source '{}': use 'github', 'gitlab' or 'gitea'",
+                    source
+                )
+            }
+            ToolError::UrlWithoutTag => {
+                "[error] 'url' requires an explicit 'tag' of the version to download".to_string()
+            }
+            ToolError::InvalidTagPattern { pattern, error } => {
+                format!("[error] Invalid 'tag_pattern' '{}': {}", pattern, error)
+            }
+            ToolError::TagAndVersion => {
+                "[error] Specify either 'tag' or 'version', not both".to_string()
+            }
+            ToolError::InvalidVersion { version, error } => {
+                format!(
+                    "[error] Invalid 'version' requirement '{}': {}",
+                    version, error
+                )
+            }
         }
     }
 }
@@ -34,20 +74,46 @@ pub enum ToolInfoTag {
     Latest,
     /// Download a specific version
     Specific(String),
+    /// Download the highest version matching the requirement, e.g. '^13'
+    Requirement(VersionReq),
 }
 
 const LATEST_VERSION: &str = "latest";
 
 impl ToolInfoTag {
-    pub fn to_str_version(&self) -> String {
+    /// Version part of the release URL in the API. Requirements have none:
+    /// they're resolved to a release from the list of all releases instead.
+    pub fn to_str_version(&self) -> Option<String> {
         match self {
-            Self::Latest => LATEST_VERSION.to_owned(),
-            Self::Specific(version) => format!("tags/{}", version),
+            Self::Latest => Some(LATEST_VERSION.to_owned()),
+            Self::Specific(version) => Some(format!("tags/{}", version)),
+            Self::Requirement(_) => None,
+        }
+    }
+}
+
+/// Where releases of a tool are published
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseSource {
+    GitHub,
+    GitLab,
+
+    /// Gitea and its forks, e.g. Forgejo on Codeberg
+    Gitea,
+}
+
+impl ReleaseSource {
+    pub fn from_name(name: &str) -> Option<ReleaseSource> {
+        match name {
+            "github" => Some(ReleaseSource::GitHub),
+            "gitlab" => Some(ReleaseSource::GitLab),
+            "gitea" => Some(ReleaseSource::Gitea),
+            _ => None,
         }
     }
 }
 
-/// All info about installing a tool from GitHub releases
+/// All info about installing a tool from releases
 #[derive(Debug, PartialEq, Eq)]
 pub struct ToolInfo {
     /// GitHub repository author
@@ -56,12 +122,75 @@ pub struct ToolInfo {
     /// GitHub repository name
     pub repo: String,
 
-    /// Executable name inside the .tar.gz or .zip archive
-    pub exe_name: String,
+    /// Executable names inside the asset
+    pub exe_name: ExeName,
 
     /// Asset name depending on the OS
     pub asset_name: AssetName,
 
     /// Version tag
     pub tag: ToolInfoTag,
+
+    /// Expected sha256 of the downloaded asset
+    pub sha256: Option<String>,
+
+    /// How to unpack the asset
+    pub asset_type: Option<String>,
+
+    /// Glob pattern of the path to the executable inside the archive
+    pub exe_path: Option<String>,
+
+    /// Glob patterns of shell completion files inside the archive
+    pub completions: Option<Vec<String>>,
+
+    /// Glob patterns of man pages inside the archive
+    pub man_pages: Option<Vec<String>>,
+
+    /// Base URL of the API, if it differs from the default one of 'source'
+    pub api_url: Option<String>,
+
+    /// Where releases are published
+    pub source: ReleaseSource,
+
+    /// Host of the instance of 'source', if it's not the public one
+    pub host: Option<String>,
+
+    /// Name of the environment variable with the token, if it's not the
+    /// default one of 'source'
+    pub token_env: Option<String>,
+
+    /// Template of the URL to download the asset from, instead of 'source'
+    pub url: Option<String>,
+
+    /// Whether pre-releases count as the latest release
+    pub prerelease: bool,
+
+    /// Regular expression the tag of the latest release must match
+    pub tag_pattern: Option<String>,
+}
+
+impl Default for ToolInfo {
+    /// Latest release from GitHub without any extra settings. The tool itself
+    /// is empty: owner, repo, executables and asset names are always set.
+    fn default() -> Self {
+        ToolInfo {
+            owner: String::new(),
+            repo: String::new(),
+            exe_name: ExeName::Multiple(Vec::new()),
+            asset_name: AssetName::default(),
+            tag: ToolInfoTag::Latest,
+            sha256: None,
+            asset_type: None,
+            exe_path: None,
+            completions: None,
+            man_pages: None,
+            api_url: None,
+            source: ReleaseSource::GitHub,
+            host: None,
+            token_env: None,
+            url: None,
+            prerelease: false,
+            tag_pattern: None,
+        }
+    }
 }
@@ -0,0 +1,88 @@
+use std::env;
+
+/// Expand placeholders in the template of a direct download URL for the
+/// given OS (as in 'std::env::consts::OS') and the architecture of the
+/// current system:
+///
+///   * {tag}: the tag as is, e.g. 'v1.2.3'
+///   * {version}: the tag without the leading 'v', e.g. '1.2.3'
+///   * {os}: 'linux', 'darwin' or 'windows'
+///   * {arch}: 'amd64', 'arm64', '386' or the architecture as is
+///   * {exe}: the executable name, e.g. 'terraform'
+///
+/// OS and architecture names follow the Go conventions, as most vendors
+/// publish assets under those names.
+pub fn expand_url(template: &str, os: &str, tag: &str, exe_name: &str) -> String {
+    template
+        .replace("{tag}", tag)
+        .replace("{version}", tag.strip_prefix('v').unwrap_or(tag))
+        .replace("{os}", go_os(os))
+        .replace("{arch}", go_arch(env::consts::ARCH))
+        .replace("{exe}", exe_name)
+}
+
+/// Name of the downloaded file: the last segment of the URL path
+pub fn url_file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit('/').next().unwrap_or_default()
+}
+
+fn go_os(os: &str) -> &str {
+    match os {
+        "macos" => "darwin",
+        other => other,
+    }
+}
+
+fn go_arch(arch: &str) -> &str {
+    match arch {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_placeholders() {
+        let template = "https://example.com/{exe}/{version}/{exe}_{tag}_{os}.zip";
+
+        assert_eq!(
+            expand_url(template, "linux", "v1.2.3", "tool"),
+            "https://example.com/tool/1.2.3/tool_v1.2.3_linux.zip"
+        );
+        assert_eq!(
+            expand_url(template, "macos", "1.2.3", "tool"),
+            "https://example.com/tool/1.2.3/tool_1.2.3_darwin.zip"
+        );
+        assert_eq!(
+            expand_url("https://example.com/{os}/{exe}", "windows", "1.0", "tool"),
+            "https://example.com/windows/tool"
+        );
+    }
+
+    #[test]
+    fn go_names() {
+        assert_eq!(go_os("linux"), "linux");
+        assert_eq!(go_os("macos"), "darwin");
+        assert_eq!(go_arch("x86_64"), "amd64");
+        assert_eq!(go_arch("aarch64"), "arm64");
+        assert_eq!(go_arch("riscv64"), "riscv64");
+    }
+
+    #[test]
+    fn file_name() {
+        assert_eq!(
+            url_file_name("https://example.com/1.2.3/tool_linux_amd64.zip"),
+            "tool_linux_amd64.zip"
+        );
+        assert_eq!(
+            url_file_name("https://example.com/download/tool.tar.gz?version=1.2.3#top"),
+            "tool.tar.gz"
+        );
+    }
+}
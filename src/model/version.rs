@@ -0,0 +1,107 @@
+use semver::{Prerelease, Version, VersionReq};
+
+/// Semantic version from a release tag. Tags often have prefixes, e.g.
+/// 'v1.2.3', 'release-1.2.3' or 'cli-v1.2.3' in monorepos, so the version
+/// is the first part of the tag starting with a digit that parses as one.
+/// Parts at the start of the tag, after 'v' or after a separator are tried
+/// before parts following other letters, so 'sdk2-v1.0' is '1.0.0' and not
+/// '2.0.0-v1.0'. Short versions like 'v1.2' are completed with zeros.
+pub fn tag_version(tag: &str) -> Option<Version> {
+    let (preferred, others): (Vec<usize>, Vec<usize>) = tag
+        .char_indices()
+        .filter(|&(i, c)| {
+            c.is_ascii_digit()
+                && !tag[..i].ends_with(|prev: char| prev.is_ascii_digit() || prev == '.')
+        })
+        .map(|(i, _)| i)
+        .partition(|&i| {
+            !tag[..i].ends_with(|prev: char| prev.is_ascii_alphabetic() && prev != 'v')
+        });
+
+    let candidates: Vec<&str> = preferred
+        .into_iter()
+        .chain(others)
+        .map(|i| &tag[i..])
+        .collect();
+
+    candidates
+        .iter()
+        .find_map(|candidate| Version::parse(candidate).ok())
+        .or_else(|| {
+            candidates
+                .iter()
+                .find_map(|candidate| pad_version(candidate))
+        })
+}
+
+/// Whether the version matches the requirement. By the semver rules,
+/// pre-release versions match only requirements with a pre-release of the
+/// same version, e.g. '>=1.0.0-rc.1'. When pre-releases are allowed, they
+/// are matched as the version they precede instead, so '^1.0' matches
+/// '1.0.0-rc.1'.
+pub fn matches_requirement(requirement: &VersionReq, version: &Version, prerelease: bool) -> bool {
+    if prerelease && !version.pre.is_empty() {
+        let release = Version {
+            pre: Prerelease::EMPTY,
+            ..version.clone()
+        };
+
+        requirement.matches(version) || requirement.matches(&release)
+    } else {
+        requirement.matches(version)
+    }
+}
+
+/// Complete a version with missing minor or patch numbers, e.g. '1.2-rc.1'
+/// becomes '1.2.0-rc.1'
+fn pad_version(version: &str) -> Option<Version> {
+    let core_end = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, rest) = version.split_at(core_end);
+    let parts = core.split('.').count();
+
+    if parts >= 3 {
+        return None;
+    }
+
+    Version::parse(&format!("{}{}{}", core, ".0".repeat(3 - parts), rest)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_of_tags() {
+        let version = |tag| tag_version(tag).map(|version| version.to_string());
+
+        assert_eq!(version("1.2.3"), Some("1.2.3".to_owned()));
+        assert_eq!(version("v1.2.3"), Some("1.2.3".to_owned()));
+        assert_eq!(version("release-1.2.3"), Some("1.2.3".to_owned()));
+        assert_eq!(version("cli-v2.3.0-rc.1"), Some("2.3.0-rc.1".to_owned()));
+        assert_eq!(version("sdk2-v1.0.0"), Some("1.0.0".to_owned()));
+        assert_eq!(version("sdk2-v1.0"), Some("1.0.0".to_owned()));
+        assert_eq!(version("v1.2-rc2"), Some("1.2.0-rc2".to_owned()));
+        assert_eq!(version("v13"), Some("13.0.0".to_owned()));
+        assert_eq!(version("go1.21"), Some("1.21.0".to_owned()));
+        assert_eq!(version("nightly"), None);
+    }
+
+    #[test]
+    fn prerelease_requirements() {
+        let requirement = VersionReq::parse("^1.0").unwrap();
+        let rc = Version::parse("1.0.0-rc.1").unwrap();
+
+        assert!(!matches_requirement(&requirement, &rc, false));
+        assert!(matches_requirement(&requirement, &rc, true));
+        assert!(matches_requirement(
+            &requirement,
+            &Version::parse("1.2.0").unwrap(),
+            false
+        ));
+        assert!(!matches_requirement(
+            &requirement,
+            &Version::parse("2.0.0-rc.1").unwrap(),
+            true
+        ));
+    }
+}
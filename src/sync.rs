@@ -1,18 +1,129 @@
 mod archive;
+mod checksum;
 mod configure;
 mod db;
 mod download;
 mod install;
+pub mod lock;
+pub mod outcome;
 mod progress;
+mod provider;
+pub mod remove;
+mod replace;
+mod report;
+mod state;
+pub mod status;
+pub mod versions;
 
-use crate::config::schema::Config;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::config::cli::{Output, OutputFormat};
+use crate::config::schema::{Config, ConfigAsset};
 use crate::sync::install::Installer;
-use crate::sync::progress::SyncProgress;
+use crate::sync::lock::LockFile;
+use crate::sync::outcome::{InstallOutcome, SyncSummary, SYNC_FAILURE_EXIT_CODE};
+use crate::sync::report::{config_reporter, mk_reporter};
 
-pub fn sync(config: Config) {
+/// Install all tools from the config, running up to 'jobs' installations
+/// concurrently, and print the summary (only on failures when quiet). Exits
+/// with a non-zero code if any tool failed. When 'lock_file' is given, install
+/// exactly the locked assets instead of resolving releases.
+pub fn sync(
+    config: Config,
+    output: Output,
+    force: bool,
+    lock_file: Option<LockFile>,
+    jobs: Option<usize>,
+) {
     if config.tools.is_empty() {
-        eprintln!(
-            r#"No tools to sync. Have you configured 'tool-sync'?
+        no_tools_message();
+    } else {
+        let reporter = config_reporter(output, &config);
+        let installer = Installer::mk(&config, reporter, force, lock_file);
+
+        let tools: Vec<(&String, &ConfigAsset)> = config.tools.iter().collect();
+
+        let jobs = jobs.unwrap_or_else(default_jobs).clamp(1, tools.len());
+        let next_tool = AtomicUsize::new(0);
+
+        let mut outcomes: Vec<(usize, InstallOutcome)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut outcomes = Vec::new();
+
+                        loop {
+                            let index = next_tool.fetch_add(1, Ordering::Relaxed);
+
+                            match tools.get(index) {
+                                None => break outcomes,
+                                Some((tool_name, config_asset)) => outcomes
+                                    .push((index, installer.install(tool_name, config_asset))),
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                // a worker panics only if an installation panicked
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+
+        outcomes.sort_by_key(|(index, _)| *index);
+
+        let summary = SyncSummary::new(
+            outcomes
+                .into_iter()
+                .map(|(index, outcome)| (tools[index].0.clone(), outcome))
+                .collect(),
+        );
+
+        match output.format {
+            OutputFormat::Human => {
+                if !output.quiet || summary.has_failures() {
+                    println!("\n{}", summary.display());
+                }
+            }
+            OutputFormat::Json => println!("{}", summary.to_json()),
+        }
+
+        if summary.has_failures() {
+            process::exit(SYNC_FAILURE_EXIT_CODE);
+        }
+    }
+}
+
+/// Number of CPUs, or a single job if it can't be detected
+fn default_jobs() -> usize {
+    thread::available_parallelism()
+        .map(|jobs| jobs.get())
+        .unwrap_or(1)
+}
+
+/// Install a single tool into the store directory from the config and return
+/// its outcome
+pub fn install(
+    config: &Config,
+    output: Output,
+    tool_name: &str,
+    config_asset: &ConfigAsset,
+) -> InstallOutcome {
+    let tag = config_asset.tag_label();
+    let reporter = mk_reporter(output, vec![tool_name.to_owned()], vec![tag]);
+    let installer = Installer::mk(config, reporter, false, None);
+
+    installer.install(tool_name, config_asset)
+}
+
+/// Print a hint about configuring 'tool-sync' when there're no tools
+pub fn no_tools_message() {
+    eprintln!(
+        r#"No tools to sync. Have you configured 'tool-sync'?
 
 Put the following into the $HOME/.tool.toml file for the simplest configuration:
 
@@ -27,21 +138,5 @@ Put the following into the $HOME/.tool.toml file for the simplest configuration:
 For more details, refer to the official documentation:
 
     * https://github.com/chshersh/tool-sync#tool-sync"#
-        );
-    } else {
-        let store_directory = config.ensure_store_directory();
-
-        let tools: Vec<String> = config.tools.keys().cloned().collect();
-        let tags: Vec<String> = config
-            .tools
-            .values()
-            .map(|config_asset| config_asset.tag.clone().unwrap_or_else(|| "latest".into()))
-            .collect();
-        let sync_progress = SyncProgress::new(tools, tags);
-        let installer = Installer::mk(store_directory, sync_progress);
-
-        for (tool_name, config_asset) in config.tools.iter() {
-            installer.install(tool_name, config_asset);
-        }
-    }
+    );
 }
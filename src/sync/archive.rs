@@ -1,27 +1,159 @@
+use bzip2::read::BzDecoder;
 use flate2::read::GzDecoder;
-use std::fs::File;
+use std::fs::{self, File};
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
+use xz2::read::XzDecoder;
 
 use crate::model::asset_name::mk_exe_name;
+use crate::model::exe_name::ExeName;
 
 pub struct Archive<'a> {
     archive_path: &'a PathBuf,
     tmp_dir: &'a Path,
-    exe_name: &'a str,
-    archive_type: ArchiveType<'a>,
+    exe_name: &'a ExeName,
+    asset_dir: &'a str,
+    exe_path: Option<&'a str>,
+    archive_type: ArchiveType,
 }
 
 /// Archive type that specifies how to unpack asset
-enum ArchiveType<'a> {
-    Exe(&'a str),
-    Zip(&'a str),
-    TarGz(&'a str),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveType {
+    /// Windows executable without archive
+    Exe,
+
+    /// Linux or macOS executable without archive
+    RawBinary,
+
+    /// .zip archive
+    Zip,
+
+    /// Compressed .tar archive
+    Tar(Compression),
+
+    /// Single compressed executable
+    Compressed(Compression),
+}
+
+/// Compression format of a .tar archive or a single file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gz,
+    Xz,
+    Bz2,
+    Zst,
+}
+
+/// Value of the 'asset_type' config option for assets that are executables
+/// without any archive
+const RAW_BINARY_ASSET_TYPE: &str = "binary";
+
+/// Supported extensions of assets. Longer extensions go first, so '.tar.gz'
+/// is matched before '.gz'.
+const EXTENSIONS: [(&str, ArchiveType); 15] = [
+    (".tar.gz", ArchiveType::Tar(Compression::Gz)),
+    (".tgz", ArchiveType::Tar(Compression::Gz)),
+    (".tar.xz", ArchiveType::Tar(Compression::Xz)),
+    (".txz", ArchiveType::Tar(Compression::Xz)),
+    (".tar.bz2", ArchiveType::Tar(Compression::Bz2)),
+    (".tbz2", ArchiveType::Tar(Compression::Bz2)),
+    (".tar.zst", ArchiveType::Tar(Compression::Zst)),
+    (".tzst", ArchiveType::Tar(Compression::Zst)),
+    (".zip", ArchiveType::Zip),
+    (".exe", ArchiveType::Exe),
+    (".gz", ArchiveType::Compressed(Compression::Gz)),
+    (".xz", ArchiveType::Compressed(Compression::Xz)),
+    (".bz2", ArchiveType::Compressed(Compression::Bz2)),
+    (".zst", ArchiveType::Compressed(Compression::Zst)),
+    (".zstd", ArchiveType::Compressed(Compression::Zst)),
+];
+
+/// Magic bytes of ELF and Mach-O (32/64-bit, both endians, universal) files
+const EXECUTABLE_MAGIC: [&[u8]; 6] = [
+    &[0x7f, b'E', b'L', b'F'],
+    &[0xfe, 0xed, 0xfa, 0xce],
+    &[0xfe, 0xed, 0xfa, 0xcf],
+    &[0xce, 0xfa, 0xed, 0xfe],
+    &[0xcf, 0xfa, 0xed, 0xfe],
+    &[0xca, 0xfe, 0xba, 0xbe],
+];
+
+/// Magic bytes of Windows .exe files. Not used for detecting the archive type
+/// because such assets already have the .exe extension.
+const WINDOWS_EXE_MAGIC: &[u8] = b"MZ";
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Magic bytes of the supported compression formats
+const COMPRESSION_MAGIC: [(&[u8], Compression); 4] = [
+    (&[0x1f, 0x8b], Compression::Gz),
+    (&[0xfd, b'7', b'z', b'X', b'Z', 0x00], Compression::Xz),
+    (b"BZh", Compression::Bz2),
+    (&[0x28, 0xb5, 0x2f, 0xfd], Compression::Zst),
+];
+
+impl ArchiveType {
+    /// Guess archive type from the extension of the asset name. Returns the
+    /// type and the asset name without the extension.
+    fn from_extension(asset_name: &str) -> Option<(ArchiveType, &str)> {
+        EXTENSIONS.iter().find_map(|(extension, archive_type)| {
+            asset_name
+                .strip_suffix(extension)
+                .map(|asset_dir| (*archive_type, asset_dir))
+        })
+    }
+
+    /// Archive type specified explicitly in the config: either 'binary' or
+    /// one of the supported extensions
+    fn from_asset_type(asset_type: &str) -> Option<ArchiveType> {
+        if asset_type == RAW_BINARY_ASSET_TYPE {
+            return Some(ArchiveType::RawBinary);
+        }
+
+        let extension = format!(".{}", asset_type.trim_start_matches('.'));
+
+        EXTENSIONS
+            .iter()
+            .find(|(known_extension, _)| *known_extension == extension)
+            .map(|(_, archive_type)| *archive_type)
+    }
+
+    /// Detect archive type from the first bytes of the downloaded file
+    fn from_magic_bytes(path: &Path) -> Option<ArchiveType> {
+        let header = read_header(path, 6).ok()?;
+
+        if EXECUTABLE_MAGIC
+            .iter()
+            .any(|magic| header.starts_with(magic))
+        {
+            return Some(ArchiveType::RawBinary);
+        }
+
+        if header.starts_with(ZIP_MAGIC) {
+            return Some(ArchiveType::Zip);
+        }
+
+        let compression = COMPRESSION_MAGIC
+            .iter()
+            .find(|(magic, _)| header.starts_with(magic))
+            .map(|(_, compression)| *compression)?;
+
+        if is_compressed_tar(path, compression) {
+            Some(ArchiveType::Tar(compression))
+        } else {
+            Some(ArchiveType::Compressed(compression))
+        }
+    }
 }
 
 pub enum UnpackError {
     IOError(std::io::Error),
     ZipError(zip::result::ZipError),
-    ExeNotFound(String),
+    ExeNotFound(String, String),
+    ExeAmbiguous(String, Vec<String>),
+    InvalidPattern(String, glob::PatternError),
+    SingleExecutable(String),
 }
 
 impl UnpackError {
@@ -29,125 +161,805 @@ impl UnpackError {
         match self {
             UnpackError::IOError(e) => format!("{}", e),
             UnpackError::ZipError(e) => format!("{}", e),
-            UnpackError::ExeNotFound(archive_name) => {
-                format!("Can't find executable in archive: {}", archive_name)
+            UnpackError::ExeNotFound(exe_name, archive_name) => {
+                format!(
+                    "Can't find executable '{}' in archive: {}",
+                    exe_name, archive_name
+                )
             }
+            UnpackError::ExeAmbiguous(archive_name, candidates) => format!(
+                "Several executables found in archive {}: {} (specify 'exe_path' in the config)",
+                archive_name,
+                candidates.join(", ")
+            ),
+            UnpackError::InvalidPattern(key, e) => format!("Invalid '{}' pattern: {}", key, e),
+            UnpackError::SingleExecutable(asset_name) => format!(
+                "Asset {} is a single executable but several are listed in 'exe_name'",
+                asset_name
+            ),
         }
     }
 }
 
 impl<'a> Archive<'a> {
+    /// Detect how to unpack the asset. The explicit 'asset_type' from the
+    /// config has priority. Otherwise the type is guessed from the asset name
+    /// extension, and magic bytes of the downloaded file are used when the
+    /// extension is unknown or doesn't match the actual contents.
     pub fn from(
         archive_path: &'a PathBuf,
         tmp_dir: &'a Path,
-        exe_name: &'a str,
+        exe_name: &'a ExeName,
         asset_name: &'a str,
+        asset_type: Option<&str>,
+        exe_path: Option<&'a str>,
     ) -> Option<Archive<'a>> {
-        let tar_gz_dir = asset_name.strip_suffix(".tar.gz");
+        let by_extension = ArchiveType::from_extension(asset_name);
+        let asset_dir = by_extension.map_or(asset_name, |(_, asset_dir)| asset_dir);
 
-        match tar_gz_dir {
-            Some(tar_gz_dir) => Some(Archive {
-                archive_path,
-                tmp_dir,
-                exe_name,
-                archive_type: ArchiveType::TarGz(tar_gz_dir),
-            }),
-            None => {
-                let zip_dir = asset_name.strip_suffix(".zip");
-
-                match zip_dir {
-                    Some(zip_dir) => Some(Archive {
-                        archive_path,
-                        tmp_dir,
-                        exe_name,
-                        archive_type: ArchiveType::Zip(zip_dir),
-                    }),
-                    None => {
-                        let exe_file = asset_name.strip_suffix(".exe");
-
-                        exe_file.map(|_| Archive {
-                            archive_path,
-                            tmp_dir,
-                            exe_name,
-                            archive_type: ArchiveType::Exe(asset_name),
-                        })
-                    }
-                }
+        let archive_type = match asset_type {
+            Some(asset_type) => ArchiveType::from_asset_type(asset_type)?,
+            None => match (by_extension, ArchiveType::from_magic_bytes(archive_path)) {
+                (_, Some(by_magic)) => by_magic,
+                (Some((by_extension, _)), None) => by_extension,
+                (None, None) => return None,
+            },
+        };
+
+        Some(Archive {
+            archive_path,
+            tmp_dir,
+            exe_name,
+            asset_dir,
+            exe_path,
+            archive_type,
+        })
+    }
+
+    /// Unpack archive and return paths to all executables of the tool
+    /// together with their installed names
+    pub fn unpack(&self) -> Result<Vec<(PathBuf, &'a str)>, UnpackError> {
+        let executables = self.exe_name.executables();
+
+        match self.archive_type {
+            // already an executable without archive: no need to unpack
+            ArchiveType::Exe | ArchiveType::RawBinary => {
+                let installed_name = self.single_executable(&executables)?;
+                Ok(vec![(self.archive_path.clone(), installed_name)])
             }
+
+            // unpack compressed .tar archive
+            ArchiveType::Tar(compression) => {
+                let unpack_dir = self.unpack_dir();
+                unpack_tar(self.archive_path, &unpack_dir, compression)
+                    .map_err(UnpackError::IOError)?;
+                self.find_executables(&unpack_dir, &executables)
+            }
+
+            // unpack .zip archive
+            ArchiveType::Zip => {
+                let unpack_dir = self.unpack_dir();
+                unpack_zip(self.archive_path, &unpack_dir)?;
+                self.find_executables(&unpack_dir, &executables)
+            }
+
+            // decompress a single file
+            ArchiveType::Compressed(compression) => {
+                let installed_name = self.single_executable(&executables)?;
+                let unpack_dir = self.unpack_dir();
+                let exe_path = unpack_dir.join(mk_exe_name(installed_name));
+
+                unpack_compressed(self.archive_path, &unpack_dir, &exe_path, compression)
+                    .map_err(UnpackError::IOError)?;
+
+                Ok(vec![(exe_path, installed_name)])
+            }
+        }
+    }
+
+    /// Assets without archive contain exactly one executable
+    fn single_executable(
+        &self,
+        executables: &[(&'a str, &'a str)],
+    ) -> Result<&'a str, UnpackError> {
+        match executables {
+            [(_, installed_name)] => Ok(installed_name),
+            _ => Err(UnpackError::SingleExecutable(format!(
+                "{}",
+                self.archive_path.display()
+            ))),
         }
     }
 
-    /// Unpack archive and return path to the executable tool
-    pub fn unpack(&self) -> Result<PathBuf, UnpackError> {
+    fn find_executables(
+        &self,
+        unpack_dir: &Path,
+        executables: &[(&'a str, &'a str)],
+    ) -> Result<Vec<(PathBuf, &'a str)>, UnpackError> {
+        let files = unpacked_files(unpack_dir).map_err(UnpackError::IOError)?;
+
+        executables
+            .iter()
+            .map(|(exe_name, installed_name)| {
+                self.find_path_to_exe(unpack_dir, &files, exe_name)
+                    .map(|exe_path| (exe_path, *installed_name))
+            })
+            .collect()
+    }
+
+    /// Find all unpacked files matching any of the glob patterns. Assets
+    /// without archive don't contain any other files. The 'key' is the
+    /// config option with the patterns for error messages.
+    pub fn find_files(&self, key: &str, patterns: &[String]) -> Result<Vec<PathBuf>, UnpackError> {
         match self.archive_type {
-            // already .exe file without archive (on Windows): no need to unpack
-            ArchiveType::Exe(exe_file) => Ok(PathBuf::from(exe_file)),
+            ArchiveType::Tar(_) | ArchiveType::Zip => {
+                let patterns = patterns
+                    .iter()
+                    .map(|pattern| compile_pattern(key, pattern))
+                    .collect::<Result<Vec<glob::Pattern>, UnpackError>>()?;
+
+                let unpack_dir = self.unpack_dir();
+                let files = unpacked_files(&unpack_dir).map_err(UnpackError::IOError)?;
+
+                Ok(files
+                    .iter()
+                    .filter(|path| {
+                        patterns
+                            .iter()
+                            .any(|pattern| pattern.matches_path_with(path, MATCH_OPTIONS))
+                    })
+                    .map(|path| unpack_dir.join(path))
+                    .collect())
+            }
+            ArchiveType::Exe | ArchiveType::RawBinary | ArchiveType::Compressed(_) => {
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Separate directory for the unpacked files, so the search for the
+    /// executable doesn't see files of other tools
+    fn unpack_dir(&self) -> PathBuf {
+        self.tmp_dir.join(format!("{}-unpacked", self.asset_dir))
+    }
+
+    /// Find the executable among the unpacked files. With 'exe_path', the
+    /// only file matching the glob is taken. If the glob matches several
+    /// files or the tool has several executables, the matching file must also
+    /// be named 'exe_name'. Otherwise, common locations are checked first, and
+    /// then all executable files named 'exe_name'.
+    fn find_path_to_exe(
+        &self,
+        unpack_dir: &Path,
+        files: &[PathBuf],
+        exe_name: &str,
+    ) -> Result<PathBuf, UnpackError> {
+        let exe_name = mk_exe_name(exe_name);
+        let has_exe_name = |path: &&PathBuf| {
+            path.file_name()
+                .is_some_and(|name| name == exe_name.as_str())
+        };
+
+        let candidates: Vec<&PathBuf> = match self.exe_path {
+            Some(exe_path) => {
+                let pattern = compile_pattern("exe_path", exe_path)?;
 
-            // unpack .tar.gz archive
-            ArchiveType::TarGz(asset_name) => {
-                unpack_tar(self.archive_path, self.tmp_dir).map_err(UnpackError::IOError)?;
-                find_path_to_exe(self.archive_path, self.tmp_dir, self.exe_name, asset_name)
+                let matched: Vec<&PathBuf> = files
+                    .iter()
+                    .filter(|path| pattern.matches_path_with(path, MATCH_OPTIONS))
+                    .collect();
+
+                if matched.len() > 1 || self.exe_name.executables().len() > 1 {
+                    matched.into_iter().filter(has_exe_name).collect()
+                } else {
+                    matched
+                }
             }
+            None => {
+                if let Some(path) = exe_paths(&exe_name, self.asset_dir)
+                    .into_iter()
+                    .find(|path| files.contains(path))
+                {
+                    return Ok(unpack_dir.join(path));
+                }
 
-            // unpack .zip archive
-            ArchiveType::Zip(asset_name) => {
-                unpack_zip(self.archive_path, self.tmp_dir)?;
-                find_path_to_exe(self.archive_path, self.tmp_dir, self.exe_name, asset_name)
+                files
+                    .iter()
+                    .filter(has_exe_name)
+                    .filter(|path| is_executable(&unpack_dir.join(path)))
+                    .collect()
             }
+        };
+
+        match candidates.as_slice() {
+            [] => Err(UnpackError::ExeNotFound(
+                exe_name,
+                format!("{}", self.archive_path.display()),
+            )),
+            [path] => Ok(unpack_dir.join(path)),
+            _ => Err(UnpackError::ExeAmbiguous(
+                format!("{}", self.archive_path.display()),
+                candidates
+                    .iter()
+                    .map(|path| format!("{}", path.display()))
+                    .collect(),
+            )),
         }
     }
 }
 
-fn unpack_tar(tar_path: &PathBuf, tmp_dir: &Path) -> Result<(), std::io::Error> {
-    // unpack tar_path to tmp_dir
+/// Read up to 'len' first bytes of a file
+fn read_header(path: &Path, len: u64) -> io::Result<Vec<u8>> {
+    let mut header = Vec::new();
+    File::open(path)?.take(len).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/// Check for the 'ustar' magic of a .tar header inside the compressed file
+fn is_compressed_tar(path: &Path, compression: Compression) -> bool {
+    let mut header = Vec::new();
+
+    let has_header = File::open(path)
+        .and_then(|file| decoder(file, compression))
+        .and_then(|decoder| decoder.take(262).read_to_end(&mut header))
+        .is_ok();
+
+    has_header && header.len() == 262 && &header[257..262] == b"ustar"
+}
+
+fn decoder(file: File, compression: Compression) -> io::Result<Box<dyn Read>> {
+    Ok(match compression {
+        Compression::Gz => Box::new(GzDecoder::new(file)),
+        Compression::Xz => Box::new(XzDecoder::new_multi_decoder(file)),
+        Compression::Bz2 => Box::new(BzDecoder::new(file)),
+        Compression::Zst => Box::new(zstd::stream::read::Decoder::new(file)?),
+    })
+}
+
+fn unpack_tar(tar_path: &PathBuf, unpack_dir: &Path, compression: Compression) -> io::Result<()> {
+    // unpack tar_path to unpack_dir
     let tar_file = File::open(tar_path)?;
-    let tar_decoder = GzDecoder::new(tar_file);
+    let tar_decoder = decoder(tar_file, compression)?;
     let mut archive = tar::Archive::new(tar_decoder);
-    archive.unpack(tmp_dir)
+    archive.unpack(unpack_dir)
+}
+
+fn unpack_compressed(
+    compressed_path: &PathBuf,
+    unpack_dir: &Path,
+    exe_path: &Path,
+    compression: Compression,
+) -> io::Result<()> {
+    let compressed_file = File::open(compressed_path)?;
+    let mut decoder = decoder(compressed_file, compression)?;
+
+    fs::create_dir_all(unpack_dir)?;
+    let mut exe_file = File::create(exe_path)?;
+    io::copy(&mut decoder, &mut exe_file)?;
+
+    Ok(())
 }
 
-fn unpack_zip(zip_path: &PathBuf, tmp_dir: &Path) -> Result<(), UnpackError> {
-    let zip_archive_file = File::open(&zip_path).map_err(UnpackError::IOError)?;
+fn unpack_zip(zip_path: &PathBuf, unpack_dir: &Path) -> Result<(), UnpackError> {
+    let zip_archive_file = File::open(zip_path).map_err(UnpackError::IOError)?;
 
     let mut archive = zip::ZipArchive::new(zip_archive_file).map_err(UnpackError::ZipError)?;
 
-    archive.extract(tmp_dir).map_err(UnpackError::ZipError)
+    archive.extract(unpack_dir).map_err(UnpackError::ZipError)
+}
+
+/// Path patterns match only within a single directory unless they use '**'
+const MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+fn compile_pattern(key: &str, pattern: &str) -> Result<glob::Pattern, UnpackError> {
+    glob::Pattern::new(pattern).map_err(|e| UnpackError::InvalidPattern(key.to_owned(), e))
+}
+
+/// All unpacked files, relative to the unpack directory, in a stable order
+fn unpacked_files(unpack_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![unpack_dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.is_file() {
+                // unwrap is safe here because all paths are inside 'unpack_dir'
+                files.push(path.strip_prefix(unpack_dir).unwrap().to_path_buf());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
 }
 
-fn find_path_to_exe(
-    archive_path: &Path,
-    tmp_dir: &Path,
-    exe_name: &str,
-    asset_name: &str,
-) -> Result<PathBuf, UnpackError> {
-    let path_candidates = exe_paths(exe_name, asset_name);
+/// Check whether the file has executable permissions or starts with the
+/// magic bytes of an ELF, Mach-O or Windows executable
+fn is_executable(path: &Path) -> bool {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
 
-    // find a path
-    for path in path_candidates {
-        // create path to the final executable
-        let mut tool_path = PathBuf::new();
-        tool_path.push(tmp_dir);
-        tool_path.push(path);
+        let has_exec_permissions = fs::metadata(path)
+            .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false);
 
-        // check if this path actually exists
-        if tool_path.is_file() {
-            return Ok(tool_path);
+        if has_exec_permissions {
+            return true;
         }
     }
 
-    Err(UnpackError::ExeNotFound(format!(
-        "{}",
-        archive_path.display()
-    )))
+    read_header(path, 4)
+        .map(|header| {
+            header.starts_with(WINDOWS_EXE_MAGIC)
+                || EXECUTABLE_MAGIC
+                    .iter()
+                    .any(|magic| header.starts_with(magic))
+        })
+        .unwrap_or(false)
 }
 
 // List of potential paths where an executable can be inside the archive
 fn exe_paths(exe_name: &str, asset_name: &str) -> Vec<PathBuf> {
-    let exe_name = mk_exe_name(exe_name);
-
     vec![
-        [asset_name, &exe_name].iter().collect(),
-        [&exe_name].iter().collect(),
-        ["bin", &exe_name].iter().collect(),
+        [asset_name, exe_name].iter().collect(),
+        [exe_name].iter().collect(),
+        ["bin", exe_name].iter().collect(),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    const TOOL_CONTENTS: &[u8] = b"#!/bin/sh\necho tool\n";
+
+    fn archive_type_of(
+        asset_name: &str,
+        contents: &[u8],
+        asset_type: Option<&str>,
+    ) -> Option<ArchiveType> {
+        let tmp_dir = TempDir::new("tool-sync-test").unwrap();
+        let archive_path = tmp_dir.path().join(asset_name);
+        fs::write(&archive_path, contents).unwrap();
+
+        Archive::from(
+            &archive_path,
+            tmp_dir.path(),
+            &ExeName::from("tool"),
+            asset_name,
+            asset_type,
+            None,
+        )
+        .map(|archive| archive.archive_type)
+    }
+
+    /// .tar archive with the 'tool' executable inside the 'tool-1.0' directory
+    fn tar_fixture() -> Vec<u8> {
+        tar_with_files(&[("tool-1.0/tool", 0o755)])
+    }
+
+    /// .tar archive with the given files and their permissions. Each file
+    /// contains its own path, so tests can check which one was found.
+    fn tar_with_files(files: &[(&str, u32)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+
+        for (path, mode) in files {
+            let contents = if path.ends_with("tool-1.0/tool") {
+                TOOL_CONTENTS
+            } else {
+                path.as_bytes()
+            };
+
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(*mode);
+            header.set_cksum();
+
+            builder.append_data(&mut header, path, contents).unwrap();
+        }
+
+        builder.into_inner().unwrap()
+    }
+
+    fn compress(data: &[u8], compression: Compression) -> Vec<u8> {
+        match compression {
+            Compression::Gz => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Bz2 => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zst => zstd::stream::encode_all(data, 0).unwrap(),
+        }
+    }
+
+    /// Write the asset to a temporary directory, unpack it and return the
+    /// contents of the found executable
+    fn unpack_fixture(asset_name: &str, contents: &[u8]) -> Vec<u8> {
+        match unpack_with_exe_path(asset_name, contents, None) {
+            Ok(exe_contents) => exe_contents,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    fn unpack_with_exe_path(
+        asset_name: &str,
+        contents: &[u8],
+        exe_path: Option<&str>,
+    ) -> Result<Vec<u8>, String> {
+        let executables =
+            unpack_executables(asset_name, contents, &ExeName::from("tool"), exe_path)?;
+
+        match executables.as_slice() {
+            [(exe_contents, installed_name)] if installed_name == "tool" => {
+                Ok(exe_contents.clone())
+            }
+            _ => panic!("Expected a single executable"),
+        }
+    }
+
+    /// Unpack the asset and return the contents and the installed names of
+    /// all found executables
+    fn unpack_executables(
+        asset_name: &str,
+        contents: &[u8],
+        exe_name: &ExeName,
+        exe_path: Option<&str>,
+    ) -> Result<Vec<(Vec<u8>, String)>, String> {
+        let tmp_dir = TempDir::new("tool-sync-test").unwrap();
+        let archive_path = tmp_dir.path().join(asset_name);
+        fs::write(&archive_path, contents).unwrap();
+
+        let archive = Archive::from(
+            &archive_path,
+            tmp_dir.path(),
+            exe_name,
+            asset_name,
+            None,
+            exe_path,
+        )
+        .expect("Unsupported asset type");
+
+        archive
+            .unpack()
+            .map(|executables| {
+                executables
+                    .into_iter()
+                    .map(|(exe_path, installed_name)| {
+                        (fs::read(exe_path).unwrap(), installed_name.to_owned())
+                    })
+                    .collect()
+            })
+            .map_err(|e| e.display())
+    }
+
+    #[test]
+    fn archive_type_by_extension() {
+        assert_eq!(
+            archive_type_of("tool-linux.tar.gz", b"", None),
+            Some(ArchiveType::Tar(Compression::Gz))
+        );
+        assert_eq!(
+            archive_type_of("tool-linux.tgz", b"", None),
+            Some(ArchiveType::Tar(Compression::Gz))
+        );
+        assert_eq!(
+            archive_type_of("tool-linux.tar.xz", b"", None),
+            Some(ArchiveType::Tar(Compression::Xz))
+        );
+        assert_eq!(
+            archive_type_of("tool-linux.tar.bz2", b"", None),
+            Some(ArchiveType::Tar(Compression::Bz2))
+        );
+        assert_eq!(
+            archive_type_of("tool-linux.tar.zst", b"", None),
+            Some(ArchiveType::Tar(Compression::Zst))
+        );
+        assert_eq!(
+            archive_type_of("tool-linux.gz", b"", None),
+            Some(ArchiveType::Compressed(Compression::Gz))
+        );
+        assert_eq!(
+            archive_type_of("tool-linux.zip", b"", None),
+            Some(ArchiveType::Zip)
+        );
+        assert_eq!(
+            archive_type_of("tool-windows.exe", b"", None),
+            Some(ArchiveType::Exe)
+        );
+    }
+
+    #[test]
+    fn raw_binary_by_magic_bytes() {
+        assert_eq!(
+            archive_type_of("yq_linux_amd64", b"\x7fELF\x02\x01\x01", None),
+            Some(ArchiveType::RawBinary)
+        );
+        assert_eq!(
+            archive_type_of("jq-osx-amd64", &[0xcf, 0xfa, 0xed, 0xfe, 0x07], None),
+            Some(ArchiveType::RawBinary)
+        );
+        assert_eq!(archive_type_of("notes.txt", b"hello, world", None), None);
+        assert_eq!(archive_type_of("empty", b"", None), None);
+    }
+
+    #[test]
+    fn explicit_asset_type() {
+        assert_eq!(
+            archive_type_of("kubectl", b"#!/bin/sh", Some("binary")),
+            Some(ArchiveType::RawBinary)
+        );
+        assert_eq!(
+            archive_type_of("tool.download", b"", Some("tar.xz")),
+            Some(ArchiveType::Tar(Compression::Xz))
+        );
+        assert_eq!(archive_type_of("tool.tar.gz", b"", Some("rar")), None);
+    }
+
+    #[test]
+    fn magic_bytes_override_misleading_extension() {
+        let tar_xz = compress(&tar_fixture(), Compression::Xz);
+
+        assert_eq!(
+            archive_type_of("tool-linux.tar.gz", &tar_xz, None),
+            Some(ArchiveType::Tar(Compression::Xz))
+        );
+        assert_eq!(
+            archive_type_of("tool-linux", &tar_xz, None),
+            Some(ArchiveType::Tar(Compression::Xz))
+        );
+        assert_eq!(
+            archive_type_of("tool-linux.gz", &tar_xz, None),
+            Some(ArchiveType::Tar(Compression::Xz))
+        );
+        assert_eq!(
+            archive_type_of(
+                "tool-linux.tar.gz",
+                &compress(TOOL_CONTENTS, Compression::Gz),
+                None
+            ),
+            Some(ArchiveType::Compressed(Compression::Gz))
+        );
+    }
+
+    #[test]
+    fn unpack_compressed_tar_archives() {
+        let tar = tar_fixture();
+
+        for (extension, compression) in [
+            ("tar.gz", Compression::Gz),
+            ("tgz", Compression::Gz),
+            ("tar.xz", Compression::Xz),
+            ("tar.bz2", Compression::Bz2),
+            ("tar.zst", Compression::Zst),
+        ] {
+            let asset_name = format!("tool-1.0.{}", extension);
+
+            assert_eq!(
+                unpack_fixture(&asset_name, &compress(&tar, compression)),
+                TOOL_CONTENTS,
+                "{}",
+                asset_name
+            );
+        }
+    }
+
+    #[test]
+    fn unpack_single_compressed_files() {
+        for (extension, compression) in [
+            ("gz", Compression::Gz),
+            ("xz", Compression::Xz),
+            ("bz2", Compression::Bz2),
+            ("zst", Compression::Zst),
+        ] {
+            let asset_name = format!("tool-linux-amd64.{}", extension);
+
+            assert_eq!(
+                unpack_fixture(&asset_name, &compress(TOOL_CONTENTS, compression)),
+                TOOL_CONTENTS,
+                "{}",
+                asset_name
+            );
+        }
+    }
+
+    #[test]
+    fn executable_is_found_recursively() {
+        let tar_gz = compress(
+            &tar_with_files(&[
+                ("tool-v1.2/README.md", 0o644),
+                ("tool-v1.2/doc/tool", 0o644),
+                ("tool-v1.2/bin/tool", 0o755),
+            ]),
+            Compression::Gz,
+        );
+
+        assert_eq!(
+            unpack_with_exe_path("tool-linux.tar.gz", &tar_gz, None),
+            Ok(b"tool-v1.2/bin/tool".to_vec())
+        );
+    }
+
+    #[test]
+    fn executable_is_found_by_exe_path() {
+        let tar_gz = compress(
+            &tar_with_files(&[
+                ("tool-v1.2/bin/tool", 0o755),
+                ("tool-v1.2/contrib/tool", 0o755),
+            ]),
+            Compression::Gz,
+        );
+
+        assert_eq!(
+            unpack_with_exe_path("tool-linux.tar.gz", &tar_gz, Some("*/bin/tool")),
+            Ok(b"tool-v1.2/bin/tool".to_vec())
+        );
+        assert!(unpack_with_exe_path("tool-linux.tar.gz", &tar_gz, Some("bin/tool")).is_err());
+    }
+
+    #[test]
+    fn several_executables_are_ambiguous() {
+        let tar_gz = compress(
+            &tar_with_files(&[
+                ("tool-v1.2/bin/tool", 0o755),
+                ("tool-v1.2/contrib/tool", 0o755),
+            ]),
+            Compression::Gz,
+        );
+
+        let error = unpack_with_exe_path("tool-linux.tar.gz", &tar_gz, None).unwrap_err();
+
+        assert!(error.contains("Several executables found"), "{}", error);
+        assert!(error.contains("tool-v1.2/bin/tool"), "{}", error);
+        assert!(error.contains("tool-v1.2/contrib/tool"), "{}", error);
+
+        let error =
+            unpack_with_exe_path("tool-linux.tar.gz", &tar_gz, Some("**/tool")).unwrap_err();
+        assert!(error.contains("Several executables found"), "{}", error);
+    }
+
+    #[test]
+    fn raw_binary_is_unpacked_to_itself() {
+        let tmp_dir = TempDir::new("tool-sync-test").unwrap();
+
+        for asset_name in ["yq_linux_amd64", "tool.exe"] {
+            let archive_path = tmp_dir.path().join(asset_name);
+            fs::write(&archive_path, b"\x7fELF").unwrap();
+
+            let exe_name = ExeName::from("tool");
+            let archive = Archive::from(
+                &archive_path,
+                tmp_dir.path(),
+                &exe_name,
+                asset_name,
+                None,
+                None,
+            )
+            .unwrap();
+
+            assert_eq!(
+                archive.unpack().ok(),
+                Some(vec![(archive_path.clone(), "tool")])
+            );
+        }
+    }
+
+    #[test]
+    fn several_executables_from_one_archive() {
+        let tar_gz = compress(
+            &tar_with_files(&[
+                ("fzf-0.34/bin/fzf", 0o755),
+                ("fzf-0.34/bin/fzf-tmux", 0o755),
+                ("fzf-0.34/man/fzf.1", 0o644),
+            ]),
+            Compression::Gz,
+        );
+
+        let exe_name = ExeName::Renamed(BTreeMap::from([
+            ("fzf".to_owned(), "fzf".to_owned()),
+            ("fzf-tmux".to_owned(), "ftmux".to_owned()),
+        ]));
+
+        assert_eq!(
+            unpack_executables("fzf-linux.tar.gz", &tar_gz, &exe_name, None),
+            Ok(vec![
+                (b"fzf-0.34/bin/fzf".to_vec(), "fzf".to_owned()),
+                (b"fzf-0.34/bin/fzf-tmux".to_vec(), "ftmux".to_owned()),
+            ])
+        );
+
+        assert_eq!(
+            unpack_executables("fzf-linux.tar.gz", &tar_gz, &exe_name, Some("*/bin/*")),
+            Ok(vec![
+                (b"fzf-0.34/bin/fzf".to_vec(), "fzf".to_owned()),
+                (b"fzf-0.34/bin/fzf-tmux".to_vec(), "ftmux".to_owned()),
+            ])
+        );
+
+        let missing = ExeName::Multiple(vec!["fzf".to_owned(), "sk".to_owned()]);
+        let error = unpack_executables("fzf-linux.tar.gz", &tar_gz, &missing, None).unwrap_err();
+        assert!(error.contains("Can't find executable 'sk'"), "{}", error);
+    }
+
+    #[test]
+    fn raw_binary_contains_single_executable() {
+        let exe_name = ExeName::Multiple(vec!["kubectx".to_owned(), "kubens".to_owned()]);
+
+        let error = unpack_executables("kubectx_linux", b"\x7fELF", &exe_name, None).unwrap_err();
+        assert!(error.contains("single executable"), "{}", error);
+    }
+
+    #[test]
+    fn find_completions_and_man_pages() {
+        let tmp_dir = TempDir::new("tool-sync-test").unwrap();
+        let asset_name = "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz";
+        let archive_path = tmp_dir.path().join(asset_name);
+
+        let tar_gz = compress(
+            &tar_with_files(&[
+                ("ripgrep-13.0.0/rg", 0o755),
+                ("ripgrep-13.0.0/complete/_rg", 0o644),
+                ("ripgrep-13.0.0/complete/rg.bash", 0o644),
+                ("ripgrep-13.0.0/doc/rg.1", 0o644),
+                ("ripgrep-13.0.0/doc/GUIDE.md", 0o644),
+            ]),
+            Compression::Gz,
+        );
+        fs::write(&archive_path, tar_gz).unwrap();
+
+        let exe_name = ExeName::from("rg");
+        let archive = Archive::from(
+            &archive_path,
+            tmp_dir.path(),
+            &exe_name,
+            asset_name,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(archive.unpack().is_ok());
+
+        let file_names = |key: &str, patterns: &[&str]| -> Vec<String> {
+            let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+
+            match archive.find_files(key, &patterns) {
+                Err(e) => panic!("{}", e.display()),
+                Ok(paths) => paths
+                    .iter()
+                    .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                    .collect(),
+            }
+        };
+
+        assert_eq!(
+            file_names("completions", &["**/complete/*"]),
+            vec!["_rg", "rg.bash"]
+        );
+        assert_eq!(file_names("man_pages", &["*/doc/*.1"]), vec!["rg.1"]);
+        assert!(file_names("man_pages", &["doc/*.1"]).is_empty());
+        assert!(archive.find_files("man_pages", &["[".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use crate::model::release::Asset;
+
+/// Compute hex-encoded sha256 of a file
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Check that the downloaded asset has the expected sha256. The 'source'
+/// describes where the expected hash comes from for the error message.
+pub fn verify_sha256(
+    path: &Path,
+    asset_name: &str,
+    expected: &str,
+    source: &str,
+) -> Result<(), String> {
+    let actual = sha256_file(path).map_err(|e| format!("{}", e))?;
+    let expected = expected.trim().to_lowercase();
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "sha256 mismatch for {} (from {}): expected {}, got {}",
+            asset_name, source, expected, actual
+        ))
+    }
+}
+
+/// Find an asset in the release that contains the checksum of the given
+/// asset: either '<asset>.sha256' or a common checksums file for all assets,
+/// e.g. 'SHA256SUMS', 'checksums.txt' or 'tool_1.0.0_checksums.sha256'.
+/// Checksums of other assets, like '<other asset>.sha256', are never used.
+pub fn find_checksum_asset<'a>(assets: &'a [Asset], asset_name: &str) -> Option<&'a Asset> {
+    let own_checksum = format!("{}.sha256", asset_name);
+
+    let is_other_checksum = |name: &str| {
+        name.strip_suffix(".sha256")
+            .is_some_and(|checksum_of| assets.iter().any(|asset| asset.name == checksum_of))
+    };
+
+    assets
+        .iter()
+        .find(|asset| asset.name == own_checksum)
+        .or_else(|| {
+            assets.iter().find(|asset| {
+                let name = asset.name.to_lowercase();
+                name == "sha256sums" || name == "sha256sums.txt" || name.ends_with("checksums.txt")
+            })
+        })
+        .or_else(|| {
+            assets.iter().find(|asset| {
+                asset.name.to_lowercase().ends_with(".sha256") && !is_other_checksum(&asset.name)
+            })
+        })
+}
+
+/// Find the hash of the asset in the contents of the checksum file with the
+/// given name. Each line is either '<hash>  <file name>' or just '<hash>'.
+/// A bare hash is trusted only in the asset's own '<asset>.sha256': other
+/// files, like the checksum of an asset for another OS, must name the asset.
+pub fn parse_checksum(contents: &str, checksum_name: &str, asset_name: &str) -> Option<String> {
+    let is_sha256 = |hash: &str| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+    let is_own_checksum = checksum_name == format!("{}.sha256", asset_name);
+
+    for line in contents.lines() {
+        let mut parts = line.split_whitespace();
+
+        let hash = match parts.next() {
+            Some(hash) if is_sha256(hash) => hash,
+            _ => continue,
+        };
+
+        match parts.next() {
+            None if is_own_checksum => return Some(hash.to_lowercase()),
+            None => continue,
+            Some(file_name) => {
+                // '*' marks files hashed in binary mode
+                let file_name = file_name.trim_start_matches('*');
+                let file_name = file_name.rsplit('/').next().unwrap_or(file_name);
+
+                if file_name == asset_name {
+                    return Some(hash.to_lowercase());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempdir::TempDir;
+
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    fn asset(name: &str) -> Asset {
+        Asset {
+            id: 1,
+            name: name.to_string(),
+            size: Some(42),
+            browser_download_url: format!("https://example.com/{}", name),
+        }
+    }
+
+    #[test]
+    fn sha256_of_file() {
+        let dir = TempDir::new("tool-sync-test").unwrap();
+        let path = dir.path().join("hello");
+        fs::write(&path, "hello").unwrap();
+
+        assert_eq!(sha256_file(&path).unwrap(), HELLO_SHA256);
+        assert!(verify_sha256(&path, "hello", &HELLO_SHA256.to_uppercase(), "config").is_ok());
+        assert!(verify_sha256(&path, "hello", &"0".repeat(64), "config").is_err());
+    }
+
+    #[test]
+    fn own_checksum_asset_is_preferred() {
+        let assets = vec![
+            asset("tool-linux.tar.gz"),
+            asset("checksums.txt"),
+            asset("tool-linux.tar.gz.sha256"),
+        ];
+
+        assert_eq!(
+            find_checksum_asset(&assets, "tool-linux.tar.gz").map(|a| a.name.as_str()),
+            Some("tool-linux.tar.gz.sha256")
+        );
+    }
+
+    #[test]
+    fn common_checksum_assets() {
+        for name in ["SHA256SUMS", "checksums.txt", "tool_1.0.0_checksums.txt"] {
+            let assets = vec![asset("tool-linux.tar.gz"), asset(name)];
+
+            assert_eq!(
+                find_checksum_asset(&assets, "tool-linux.tar.gz").map(|a| a.name.as_str()),
+                Some(name)
+            );
+        }
+
+        let assets = vec![asset("tool-linux.tar.gz"), asset("tool-macos.tar.gz")];
+        assert!(find_checksum_asset(&assets, "tool-linux.tar.gz").is_none());
+    }
+
+    #[test]
+    fn any_sha256_asset() {
+        let assets = vec![
+            asset("tool-linux.tar.gz"),
+            asset("tool-macos.tar.gz"),
+            asset("tool-macos.tar.gz.sha256"),
+            asset("tool_1.0.0_SHA256SUMS.sha256"),
+        ];
+
+        assert_eq!(
+            find_checksum_asset(&assets, "tool-linux.tar.gz").map(|a| a.name.as_str()),
+            Some("tool_1.0.0_SHA256SUMS.sha256")
+        );
+
+        // the checksum of another asset doesn't verify this one
+        let assets = vec![
+            asset("tool-linux.tar.gz"),
+            asset("tool-macos.tar.gz"),
+            asset("tool-macos.tar.gz.sha256"),
+        ];
+        assert!(find_checksum_asset(&assets, "tool-linux.tar.gz").is_none());
+    }
+
+    #[test]
+    fn parse_checksums_file() {
+        let contents = format!(
+            "{}  tool-macos.tar.gz\n{} *tool-linux.tar.gz\n",
+            "0".repeat(64),
+            HELLO_SHA256
+        );
+
+        assert_eq!(
+            parse_checksum(&contents, "checksums.txt", "tool-linux.tar.gz"),
+            Some(HELLO_SHA256.to_string())
+        );
+        assert_eq!(
+            parse_checksum(&contents, "checksums.txt", "tool-windows.zip"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_single_hash() {
+        let contents = format!("{}\n", HELLO_SHA256.to_uppercase());
+
+        assert_eq!(
+            parse_checksum(&contents, "tool-linux.tar.gz.sha256", "tool-linux.tar.gz"),
+            Some(HELLO_SHA256.to_string())
+        );
+    }
+
+    #[test]
+    fn checksum_of_another_platform() {
+        let assets = vec![
+            asset("tool-linux.tar.gz"),
+            asset("tool-macos.tar.gz"),
+            asset("tool-macos.sha256"),
+        ];
+        let checksum_asset = find_checksum_asset(&assets, "tool-linux.tar.gz").unwrap();
+        let contents = format!("{}\n", "0".repeat(64));
+
+        // the bare hash belongs to the macOS asset, so the Linux one isn't verified
+        assert_eq!(
+            parse_checksum(&contents, &checksum_asset.name, "tool-linux.tar.gz"),
+            None
+        );
+    }
+}
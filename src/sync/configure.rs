@@ -1,9 +1,27 @@
+use regex::Regex;
+use semver::VersionReq;
+
 use crate::config::schema::ConfigAsset;
 use crate::model::asset_name::AssetName;
-use crate::model::tool::{Tool, ToolError, ToolInfo, ToolInfoTag};
+use crate::model::exe_name::ExeName;
+use crate::model::tool::{ReleaseSource, Tool, ToolError, ToolInfo, ToolInfoTag};
+use crate::model::url_template::{expand_url, url_file_name};
 use crate::sync::db::lookup_tool;
 
 pub fn configure_tool(tool_name: &str, config_asset: &ConfigAsset) -> Tool {
+    if let Err(error) = check_config_asset(config_asset) {
+        return Tool::Error(error);
+    }
+
+    // Direct URLs don't depend on any repository, so they're configured
+    // the same way for known and unknown tools
+    if let Some(url) = &config_asset.url {
+        return match &config_asset.tag {
+            Some(tag) => Tool::Known(url_configure(tool_name, url, tag, config_asset)),
+            None => Tool::Error(ToolError::UrlWithoutTag),
+        };
+    }
+
     match lookup_tool(tool_name) {
         // This is a known tool; we get hardcoded info and update it with config
         Some(tool_info) => Tool::Known(tool_info.configure(config_asset)),
@@ -27,6 +45,41 @@ pub fn configure_tool(tool_name: &str, config_asset: &ConfigAsset) -> Tool {
     }
 }
 
+/// Check values of keys that are the same for known and unknown tools
+fn check_config_asset(config_asset: &ConfigAsset) -> Result<(), ToolError> {
+    if let Some(source) = &config_asset.source {
+        if ReleaseSource::from_name(source).is_none() {
+            return Err(ToolError::UnknownSource {
+                source: source.clone(),
+            });
+        }
+    }
+
+    if let Some(pattern) = &config_asset.tag_pattern {
+        if let Err(error) = Regex::new(pattern) {
+            return Err(ToolError::InvalidTagPattern {
+                pattern: pattern.clone(),
+                error: error.to_string(),
+            });
+        }
+    }
+
+    if let Some(version) = &config_asset.version {
+        if config_asset.tag.is_some() {
+            return Err(ToolError::TagAndVersion);
+        }
+
+        if let Err(error) = VersionReq::parse(version) {
+            return Err(ToolError::InvalidVersion {
+                version: version.clone(),
+                error: error.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
 /// Configure 'ToolInfo' completely from 'ConfigAsset'
 fn full_configure(config_asset: &ConfigAsset) -> Option<ToolInfo> {
     let owner = config_asset.owner.clone()?;
@@ -34,12 +87,8 @@ fn full_configure(config_asset: &ConfigAsset) -> Option<ToolInfo> {
     let exe_name = config_asset
         .exe_name
         .clone()
-        .unwrap_or(config_asset.repo.clone()?);
-    let tag = config_asset
-        .tag
-        .clone()
-        .map(ToolInfoTag::Specific)
-        .unwrap_or(ToolInfoTag::Latest);
+        .unwrap_or(ExeName::Single(config_asset.repo.clone()?));
+    let tag = config_tag(config_asset);
 
     Some(ToolInfo {
         owner,
@@ -51,9 +100,67 @@ fn full_configure(config_asset: &ConfigAsset) -> Option<ToolInfo> {
             windows: config_asset.asset_name.windows.clone(),
         },
         tag,
+        sha256: config_asset.sha256.clone(),
+        asset_type: config_asset.asset_type.clone(),
+        exe_path: config_asset.exe_path.clone(),
+        completions: config_asset.completions.clone(),
+        man_pages: config_asset.man_pages.clone(),
+        api_url: config_asset.api_url.clone(),
+        source: config_source(config_asset).unwrap_or(ReleaseSource::GitHub),
+        host: config_asset.host.clone(),
+        token_env: config_asset.token_env.clone(),
+        url: config_asset.url.clone(),
+        prerelease: config_asset.prerelease.unwrap_or(false),
+        tag_pattern: config_asset.tag_pattern.clone(),
     })
 }
 
+/// Configure 'ToolInfo' to download the asset from the URL template. Asset
+/// names default to file names in the URL for each OS.
+fn url_configure(tool_name: &str, url: &str, tag: &str, config_asset: &ConfigAsset) -> ToolInfo {
+    let exe_name = config_asset
+        .exe_name
+        .clone()
+        .unwrap_or_else(|| ExeName::from(tool_name));
+
+    let exe = exe_name
+        .executables()
+        .first()
+        .map_or(tool_name, |(name, _)| *name);
+
+    let asset_name_for = |os: &str, configured: &Option<String>| {
+        configured
+            .clone()
+            .or_else(|| Some(url_file_name(&expand_url(url, os, tag, exe)).to_owned()))
+    };
+
+    let asset_name = AssetName {
+        linux: asset_name_for("linux", &config_asset.asset_name.linux),
+        macos: asset_name_for("macos", &config_asset.asset_name.macos),
+        windows: asset_name_for("windows", &config_asset.asset_name.windows),
+    };
+
+    ToolInfo {
+        owner: config_asset.owner.clone().unwrap_or_default(),
+        repo: config_asset.repo.clone().unwrap_or_default(),
+        exe_name,
+        asset_name,
+        tag: ToolInfoTag::Specific(tag.to_owned()),
+        sha256: config_asset.sha256.clone(),
+        asset_type: config_asset.asset_type.clone(),
+        exe_path: config_asset.exe_path.clone(),
+        completions: config_asset.completions.clone(),
+        man_pages: config_asset.man_pages.clone(),
+        api_url: None,
+        source: ReleaseSource::GitHub,
+        host: None,
+        token_env: None,
+        url: Some(url.to_owned()),
+        prerelease: false,
+        tag_pattern: None,
+    }
+}
+
 impl ToolInfo {
     /// Update hardcoded tool info with configuration from TOML
     pub fn configure(&self, config_asset: &ConfigAsset) -> ToolInfo {
@@ -69,7 +176,7 @@ impl ToolInfo {
             exe_name: config_asset
                 .exe_name
                 .clone()
-                .unwrap_or(config_asset.repo.clone().unwrap()),
+                .unwrap_or_else(|| self.exe_name.clone()),
             asset_name: AssetName {
                 linux: config_asset
                     .asset_name
@@ -87,15 +194,64 @@ impl ToolInfo {
                     .clone()
                     .or_else(|| self.asset_name.windows.clone()),
             },
-            tag: config_asset
-                .tag
+            tag: config_tag(config_asset),
+            sha256: config_asset.sha256.clone().or_else(|| self.sha256.clone()),
+            asset_type: config_asset
+                .asset_type
+                .clone()
+                .or_else(|| self.asset_type.clone()),
+            exe_path: config_asset
+                .exe_path
                 .clone()
-                .map(|version| ToolInfoTag::Specific(version))
-                .unwrap_or(ToolInfoTag::Latest),
+                .or_else(|| self.exe_path.clone()),
+            completions: config_asset
+                .completions
+                .clone()
+                .or_else(|| self.completions.clone()),
+            man_pages: config_asset
+                .man_pages
+                .clone()
+                .or_else(|| self.man_pages.clone()),
+            api_url: config_asset
+                .api_url
+                .clone()
+                .or_else(|| self.api_url.clone()),
+            source: config_source(config_asset).unwrap_or(self.source),
+            host: config_asset.host.clone().or_else(|| self.host.clone()),
+            token_env: config_asset
+                .token_env
+                .clone()
+                .or_else(|| self.token_env.clone()),
+            url: config_asset.url.clone().or_else(|| self.url.clone()),
+            prerelease: config_asset.prerelease.unwrap_or(self.prerelease),
+            tag_pattern: config_asset
+                .tag_pattern
+                .clone()
+                .or_else(|| self.tag_pattern.clone()),
         }
     }
 }
 
+/// Tag or version requirement from the config. Invalid requirements are
+/// reported by 'check_config_asset' before.
+fn config_tag(config_asset: &ConfigAsset) -> ToolInfoTag {
+    match (&config_asset.tag, &config_asset.version) {
+        (Some(tag), _) => ToolInfoTag::Specific(tag.clone()),
+        (None, Some(version)) => VersionReq::parse(version)
+            .map(ToolInfoTag::Requirement)
+            .unwrap_or(ToolInfoTag::Latest),
+        (None, None) => ToolInfoTag::Latest,
+    }
+}
+
+/// Source from the config, if it's specified and valid
+fn config_source(config_asset: &ConfigAsset) -> Option<ReleaseSource> {
+    config_asset
+        .source
+        .as_deref()
+        .and_then(ReleaseSource::from_name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,6 +270,7 @@ mod tests {
                 windows: None,
             },
             tag: None,
+            ..ConfigAsset::default()
         };
 
         assert_eq!(
@@ -136,6 +293,7 @@ mod tests {
                 windows: None,
             },
             tag: None,
+            ..ConfigAsset::default()
         };
 
         assert_eq!(
@@ -158,6 +316,7 @@ mod tests {
                 windows: None,
             },
             tag: None,
+            ..ConfigAsset::default()
         };
 
         assert_eq!(
@@ -175,13 +334,14 @@ mod tests {
         let config_asset = ConfigAsset {
             owner: Some(String::from("chshersh")),
             repo: None,
-            exe_name: Some(String::from("abcdefu")),
+            exe_name: Some(ExeName::from("abcdefu")),
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
             },
             tag: Some(String::from("1.2.3")),
+            ..ConfigAsset::default()
         };
 
         assert_eq!(
@@ -197,13 +357,14 @@ mod tests {
         let config_asset = ConfigAsset {
             owner: Some(String::from("chshersh")),
             repo: Some(String::from("Pluto")),
-            exe_name: Some(String::from("abcdefu")),
+            exe_name: Some(ExeName::from("abcdefu")),
             asset_name: AssetName {
                 linux: Some(String::from("my-linux")),
                 macos: Some(String::from("my-macos")),
                 windows: Some(String::from("yours-windows")),
             },
             tag: Some(String::from("1.2.3")),
+            ..ConfigAsset::default()
         };
 
         assert_eq!(
@@ -211,13 +372,14 @@ mod tests {
             Tool::Known(ToolInfo {
                 owner: "chshersh".to_string(),
                 repo: "Pluto".to_string(),
-                exe_name: "abcdefu".to_string(),
+                exe_name: ExeName::from("abcdefu"),
                 asset_name: AssetName {
                     linux: Some("my-linux".to_string()),
                     macos: Some("my-macos".to_string()),
                     windows: Some("yours-windows".to_string()),
                 },
                 tag: ToolInfoTag::Specific("1.2.3".to_string()),
+                ..ToolInfo::default()
             })
         );
     }
@@ -236,6 +398,7 @@ mod tests {
                 windows: Some(String::from("yours-windows")),
             },
             tag: Some(String::from("1.0.0")),
+            ..ConfigAsset::default()
         };
 
         assert_eq!(
@@ -243,13 +406,14 @@ mod tests {
             Tool::Known(ToolInfo {
                 owner: "chshersh".to_string(),
                 repo: "tool-sync".to_string(),
-                exe_name: "tool-sync".to_string(),
+                exe_name: ExeName::from("tool-sync"),
                 asset_name: AssetName {
                     linux: Some("my-linux".to_string()),
                     macos: Some("my-macos".to_string()),
                     windows: Some("yours-windows".to_string()),
                 },
                 tag: ToolInfoTag::Specific("1.0.0".to_string()),
+                ..ToolInfo::default()
             })
         );
     }
@@ -261,13 +425,14 @@ mod tests {
         let config_asset = ConfigAsset {
             owner: Some(String::from("chshersh")),
             repo: None,
-            exe_name: Some(String::from("abcdefu")),
+            exe_name: Some(ExeName::from("abcdefu")),
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
             },
             tag: None,
+            ..ConfigAsset::default()
         };
 
         assert_eq!(
@@ -275,13 +440,15 @@ mod tests {
             Tool::Known(ToolInfo {
                 owner: "chshersh".to_string(),
                 repo: "ripgrep".to_string(),
-                exe_name: "abcdefu".to_string(),
+                exe_name: ExeName::from("abcdefu"),
                 asset_name: AssetName {
                     linux: Some("unknown-linux-musl".to_string()),
                     macos: Some("apple-darwin".to_string()),
                     windows: Some("x86_64-pc-windows-msvc".to_string()),
                 },
-                tag: ToolInfoTag::Latest,
+                completions: Some(vec!["**/complete/*".to_string()]),
+                man_pages: Some(vec!["**/doc/rg.1".to_string()]),
+                ..ToolInfo::default()
             })
         );
     }
@@ -293,13 +460,16 @@ mod tests {
         let config_asset = ConfigAsset {
             owner: Some(String::from("chshersh")),
             repo: Some(String::from("Pluto")),
-            exe_name: Some(String::from("abcdefu")),
+            exe_name: Some(ExeName::from("abcdefu")),
             asset_name: AssetName {
                 linux: Some(String::from("my-linux")),
                 macos: Some(String::from("my-macos")),
                 windows: Some(String::from("yours-windows")),
             },
             tag: Some(String::from("3.2.1")),
+            completions: Some(vec![]),
+            man_pages: Some(vec![String::from("man/*")]),
+            ..ConfigAsset::default()
         };
 
         assert_eq!(
@@ -307,14 +477,163 @@ mod tests {
             Tool::Known(ToolInfo {
                 owner: "chshersh".to_string(),
                 repo: "Pluto".to_string(),
-                exe_name: "abcdefu".to_string(),
+                exe_name: ExeName::from("abcdefu"),
                 asset_name: AssetName {
                     linux: Some("my-linux".to_string()),
                     macos: Some("my-macos".to_string()),
                     windows: Some("yours-windows".to_string()),
                 },
                 tag: ToolInfoTag::Specific("3.2.1".to_string()),
+                completions: Some(vec![]),
+                man_pages: Some(vec!["man/*".to_string()]),
+                ..ToolInfo::default()
+            })
+        );
+    }
+
+    #[test]
+    fn gitlab_source() {
+        let tool_name = "abcdef";
+
+        let config_asset = ConfigAsset {
+            owner: Some(String::from("chshersh")),
+            repo: Some(String::from("Pluto")),
+            exe_name: None,
+            asset_name: AssetName {
+                linux: Some(String::from("my-linux")),
+                macos: None,
+                windows: None,
+            },
+            tag: None,
+            source: Some(String::from("gitlab")),
+            host: Some(String::from("gitlab.example.com")),
+            ..ConfigAsset::default()
+        };
+
+        assert_eq!(
+            configure_tool(tool_name, &config_asset),
+            Tool::Known(ToolInfo {
+                owner: "chshersh".to_string(),
+                repo: "Pluto".to_string(),
+                exe_name: ExeName::from("Pluto"),
+                asset_name: AssetName {
+                    linux: Some("my-linux".to_string()),
+                    macos: None,
+                    windows: None,
+                },
+                source: ReleaseSource::GitLab,
+                host: Some("gitlab.example.com".to_string()),
+                ..ToolInfo::default()
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_source() {
+        let config_asset = ConfigAsset {
+            source: Some(String::from("sourceforge")),
+            ..ConfigAsset::default()
+        };
+
+        assert_eq!(
+            configure_tool("ripgrep", &config_asset),
+            Tool::Error(ToolError::UnknownSource {
+                source: "sourceforge".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn url_source() {
+        let config_asset = ConfigAsset {
+            tag: Some(String::from("1.2.3")),
+            url: Some(String::from("https://example.com/{version}/{exe}_{os}.zip")),
+            ..ConfigAsset::default()
+        };
+
+        assert_eq!(
+            configure_tool("terraform", &config_asset),
+            Tool::Known(ToolInfo {
+                owner: "".to_string(),
+                repo: "".to_string(),
+                exe_name: ExeName::from("terraform"),
+                asset_name: AssetName {
+                    linux: Some("terraform_linux.zip".to_string()),
+                    macos: Some("terraform_darwin.zip".to_string()),
+                    windows: Some("terraform_windows.zip".to_string()),
+                },
+                tag: ToolInfoTag::Specific("1.2.3".to_string()),
+                url: Some("https://example.com/{version}/{exe}_{os}.zip".to_string()),
+                ..ToolInfo::default()
             })
         );
     }
+
+    #[test]
+    fn url_without_tag() {
+        let config_asset = ConfigAsset {
+            url: Some(String::from("https://example.com/{version}/ripgrep.zip")),
+            ..ConfigAsset::default()
+        };
+
+        assert_eq!(
+            configure_tool("ripgrep", &config_asset),
+            Tool::Error(ToolError::UrlWithoutTag)
+        );
+    }
+
+    #[test]
+    fn invalid_tag_pattern() {
+        let config_asset = ConfigAsset {
+            tag_pattern: Some(String::from("^cli-v(")),
+            ..ConfigAsset::default()
+        };
+
+        assert!(matches!(
+            configure_tool("ripgrep", &config_asset),
+            Tool::Error(ToolError::InvalidTagPattern { pattern, .. }) if pattern == "^cli-v("
+        ));
+    }
+
+    #[test]
+    fn version_requirement() {
+        let config_asset = ConfigAsset {
+            version: Some(String::from(">=0.22, <0.24")),
+            ..ConfigAsset::default()
+        };
+
+        let tool_info = match configure_tool("ripgrep", &config_asset) {
+            Tool::Known(tool_info) => tool_info,
+            Tool::Error(e) => panic!("{}", e.display()),
+        };
+
+        assert_eq!(
+            tool_info.tag,
+            ToolInfoTag::Requirement(VersionReq::parse(">=0.22, <0.24").unwrap())
+        );
+    }
+
+    #[test]
+    fn invalid_version_requirement() {
+        let with_tag = ConfigAsset {
+            tag: Some(String::from("13.0.0")),
+            version: Some(String::from("^13")),
+            ..ConfigAsset::default()
+        };
+
+        assert_eq!(
+            configure_tool("ripgrep", &with_tag),
+            Tool::Error(ToolError::TagAndVersion)
+        );
+
+        let invalid = ConfigAsset {
+            version: Some(String::from("thirteen")),
+            ..ConfigAsset::default()
+        };
+
+        assert!(matches!(
+            configure_tool("ripgrep", &invalid),
+            Tool::Error(ToolError::InvalidVersion { version, .. }) if version == "thirteen"
+        ));
+    }
 }
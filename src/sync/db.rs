@@ -1,5 +1,6 @@
 use crate::model::asset_name::AssetName;
-use crate::model::tool::{ToolInfo, ToolInfoTag};
+use crate::model::exe_name::ExeName;
+use crate::model::tool::ToolInfo;
 
 /// Get info about known tools from a hardcoded database
 pub fn lookup_tool(tool_name: &str) -> Option<ToolInfo> {
@@ -7,79 +8,87 @@ pub fn lookup_tool(tool_name: &str) -> Option<ToolInfo> {
         "bat" => Some(ToolInfo {
             owner: "sharkdp".to_string(),
             repo: "bat".to_string(),
-            exe_name: "bat".to_string(),
+            exe_name: ExeName::from("bat"),
             asset_name: AssetName {
                 linux: Some("x86_64-unknown-linux-musl".to_string()),
                 macos: Some("x86_64-apple-darwin".to_string()),
                 windows: Some("x86_64-pc-windows-msvc".to_string()),
             },
-            tag: ToolInfoTag::Latest,
+            completions: Some(vec!["**/autocomplete/*".to_string()]),
+            man_pages: Some(vec!["**/bat.1".to_string()]),
+            ..ToolInfo::default()
         }),
         "difftastic" => Some(ToolInfo {
             owner: "Wilfred".to_string(),
             repo: "difftastic".to_string(),
-            exe_name: "difft".to_string(),
+            exe_name: ExeName::from("difft"),
             asset_name: AssetName {
                 linux: Some("x86_64-unknown-linux-gnu".to_string()),
                 macos: Some("x86_64-apple-darwin".to_string()),
                 windows: Some("x86_64-pc-windows-msvc".to_string()),
             },
-            tag: ToolInfoTag::Latest,
+            ..ToolInfo::default()
         }),
         "exa" => Some(ToolInfo {
             owner: "ogham".to_string(),
             repo: "exa".to_string(),
-            exe_name: "exa".to_string(),
+            exe_name: ExeName::from("exa"),
             asset_name: AssetName {
                 linux: Some("linux-x86_64-musl".to_string()),
                 macos: Some("macos-x86_64".to_string()),
                 windows: None,
             },
-            tag: ToolInfoTag::Latest,
+            completions: Some(vec!["**/completions/*".to_string()]),
+            man_pages: Some(vec!["**/man/*".to_string()]),
+            ..ToolInfo::default()
         }),
         "fd" => Some(ToolInfo {
             owner: "sharkdp".to_string(),
             repo: "fd".to_string(),
-            exe_name: "fd".to_string(),
+            exe_name: ExeName::from("fd"),
             asset_name: AssetName {
                 linux: Some("x86_64-unknown-linux-musl".to_string()),
                 macos: Some("x86_64-apple-darwin".to_string()),
                 windows: Some("x86_64-pc-windows-msvc".to_string()),
             },
-            tag: ToolInfoTag::Latest,
+            completions: Some(vec!["**/autocomplete/*".to_string()]),
+            man_pages: Some(vec!["**/fd.1".to_string()]),
+            ..ToolInfo::default()
         }),
         "ripgrep" => Some(ToolInfo {
             owner: "BurntSushi".to_string(),
             repo: "ripgrep".to_string(),
-            exe_name: "rg".to_string(),
+            exe_name: ExeName::from("rg"),
             asset_name: AssetName {
                 linux: Some("unknown-linux-musl".to_string()),
                 macos: Some("apple-darwin".to_string()),
                 windows: Some("x86_64-pc-windows-msvc".to_string()),
             },
-            tag: ToolInfoTag::Latest,
+            completions: Some(vec!["**/complete/*".to_string()]),
+            man_pages: Some(vec!["**/doc/rg.1".to_string()]),
+            ..ToolInfo::default()
         }),
         "tool-sync" => Some(ToolInfo {
             owner: "chshersh".to_string(),
             repo: "tool-sync".to_string(),
-            exe_name: "tool".to_string(),
+            exe_name: ExeName::from("tool"),
             asset_name: AssetName {
                 linux: Some("x86_64-unknown-linux-gnu".to_string()),
                 macos: Some("x86_64-apple-darwin".to_string()),
                 windows: Some("x86_64-pc-windows-msvc".to_string()),
             },
-            tag: ToolInfoTag::Latest,
+            ..ToolInfo::default()
         }),
         // "tokei" => Some(ToolInfo {
         //     owner: "XAMPPRocky".to_string(),
         //     repo: "tokei".to_string(),
-        //     exe_name: "tokei".to_string(),
+        //     exe_name: ExeName::from("tokei"),
         //     asset_name: AssetName {
         //         linux: Some("x86_64-unknown-linux-musl".to_string()),
         //         macos: Some("apple-darwin".to_string()),
         //         windows: Some("x86_64-pc-windows-msvc".to_string()),
-        //       }
-        //     tag: ToolInfoTag::Latest,
+        //     },
+        //     ..ToolInfo::default()
         // }),
         _ => None,
     }
@@ -1,165 +1,575 @@
-use indicatif::ProgressBar;
-use std::env;
+use regex::Regex;
+use std::cell::Cell;
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 
-use crate::model::release::{Asset, Release};
-use crate::sync::progress::SyncProgress;
+use crate::model::release::{latest_release, Asset, Release};
+use crate::model::tool::ToolInfoTag;
+use crate::sync::provider::Provider;
+use crate::sync::report::{Event, Reporter};
 
+/// Downloads releases of a single tool from its provider
 pub struct Downloader<'a> {
+    pub provider: &'a Provider,
     pub owner: &'a str,
     pub repo: &'a str,
     pub asset_name: &'a str,
-    pub version: &'a str,
-    pub pb_msg: &'a ProgressBar,
-    pub sync_progress: &'a SyncProgress,
+    pub tag: &'a ToolInfoTag,
+    pub prerelease: bool,
+    pub tag_pattern: Option<&'a str>,
+    pub tool_name: &'a str,
+    pub reporter: &'a dyn Reporter,
 }
 
 /// Info about the downloaded asset
 pub struct DownloadInfo {
     pub archive_path: PathBuf,
     pub asset_name: String,
+    pub asset_size: u64,
     pub tag_name: String,
 }
 
 impl<'a> Downloader<'a> {
-    fn release_url(&self) -> String {
-        format!(
-            "https://api.github.com/repos/{owner}/{repo}/releases/{version}",
-            owner = self.owner,
-            repo = self.repo,
-            version = self.version,
-        )
-    }
+    /// Fetch info about the release without downloading any assets. Version
+    /// requirements are resolved to the highest matching release from the
+    /// list of all releases. So is the latest release if it must be a
+    /// pre-release or match the tag pattern, as APIs don't support either.
+    pub fn download_release(&self) -> Result<Release, Box<dyn Error>> {
+        self.reporter.report(self.tool_name, Event::FetchingRelease);
 
-    fn asset_url(&self, asset_id: u32) -> String {
-        format!(
-            "https://api.github.com/repos/{owner}/{repo}/releases/assets/{asset_id}",
-            owner = self.owner,
-            repo = self.repo,
-            asset_id = asset_id
-        )
+        let requirement = match self.tag {
+            ToolInfoTag::Requirement(requirement) => Some(requirement),
+            ToolInfoTag::Latest if self.prerelease || self.tag_pattern.is_some() => None,
+            tag => return self.provider.fetch_release(self.owner, self.repo, tag),
+        };
+
+        let tag_pattern = self.tag_pattern.map(Regex::new).transpose()?;
+        let releases = self.provider.fetch_releases(self.owner, self.repo)?;
+
+        match latest_release(releases, self.prerelease, tag_pattern.as_ref(), requirement) {
+            Some(release) => Ok(release),
+            None => Err(match (requirement, self.tag_pattern) {
+                (Some(requirement), _) => {
+                    format!("No release with version matching: {}", requirement)
+                }
+                (None, Some(tag_pattern)) => {
+                    format!("No release with tag matching: {}", tag_pattern)
+                }
+                (None, None) => "No releases found".to_owned(),
+            }
+            .into()),
+        }
     }
 
-    fn download_release(&self) -> Result<Release, Box<dyn Error>> {
-        let release_url = self.release_url();
+    /// Download the asset and return its path with the number of downloaded
+    /// bytes
+    fn download_asset(
+        &self,
+        tmp_dir: &Path,
+        asset: &Asset,
+    ) -> Result<(PathBuf, u64), Box<dyn Error>> {
+        let response = self
+            .provider
+            .asset_request(self.owner, self.repo, asset)
+            .call()?;
+
+        let download_path = tmp_dir.join(&asset.name);
 
-        let req = add_auth_header(
-            ureq::get(&release_url)
-                .set("Accept", "application/vnd.github+json")
-                .set("User-Agent", "chshersh/tool-sync-0.1.0"),
+        self.reporter.report(
+            self.tool_name,
+            Event::DownloadStarted {
+                asset_name: &asset.name,
+                size: asset.size,
+            },
         );
 
-        let release: Release = req.call()?.into_json()?;
+        // report only whole percents (or mebibytes if the size is unknown),
+        // so scripts don't get an event per chunk
+        let step = asset.size.map_or(1024 * 1024, |size| (size / 100).max(1));
+        let reported_steps = Cell::new(0);
+        let on_progress = |downloaded: u64| {
+            let steps = downloaded / step;
 
-        Ok(release)
-    }
+            if steps != reported_steps.get() {
+                reported_steps.set(steps);
+                self.reporter.report(
+                    self.tool_name,
+                    Event::DownloadProgress {
+                        downloaded,
+                        size: asset.size,
+                    },
+                );
+            }
+        };
 
-    fn download_asset(&self, tmp_dir: &Path, asset: &Asset) -> Result<PathBuf, Box<dyn Error>> {
-        let asset_url = self.asset_url(asset.id);
+        let downloaded = stream_to_file(response, &download_path, asset.size, &on_progress)?;
 
-        let req = add_auth_header(
-            ureq::get(&asset_url)
-                .set("Accept", "application/octet-stream")
-                .set("User-Agent", "chshersh/tool-sync-0.1.0"),
+        self.reporter.report(
+            self.tool_name,
+            Event::DownloadFinished {
+                asset_name: &asset.name,
+            },
         );
 
-        let mut stream = req.call()?.into_reader();
+        Ok((download_path, downloaded))
+    }
 
-        let download_path = tmp_dir.join(&asset.name);
-        let mut destination = File::create(&download_path)?;
+    /// Find the asset matching the configured asset name in the release
+    pub fn find_asset<'r>(&self, release: &'r Release) -> Result<&'r Asset, Box<dyn Error>> {
+        self.find_in_assets(&release.tag_name, &release.assets)
+    }
 
-        self.pb_msg.set_message("Downloading...");
-        let pb_downloading = self.sync_progress.create_progress_bar(asset.size);
+    /// Same as 'find_asset' but for the given OS instead of the current one.
+    /// Releases from URL templates only have the asset for the current OS, so
+    /// the asset for another OS is expanded from the template.
+    pub fn find_os_asset(&self, release: &Release, os: &str) -> Result<Asset, Box<dyn Error>> {
+        match self.provider.url_asset(&release.tag_name, os) {
+            Some(asset) => self.find_in_assets(&release.tag_name, &[asset]).cloned(),
+            None => self.find_asset(release).cloned(),
+        }
+    }
 
-        let mut buffer = [0; 4096];
-        while let Ok(bytes_read) = stream.read(&mut buffer) {
-            if bytes_read == 0 {
-                break;
-            }
+    fn find_in_assets<'r>(
+        &self,
+        tag_name: &str,
+        assets: &'r [Asset],
+    ) -> Result<&'r Asset, Box<dyn Error>> {
+        let asset = assets
+            .iter()
+            .find(|&asset| asset.name.contains(self.asset_name))
+            .ok_or_else(|| format!("No asset matching name: {}", self.asset_name))?;
 
-            pb_downloading.inc(bytes_read as u64);
-            destination.write_all(&buffer[..bytes_read])?;
-        }
+        self.reporter.report(
+            self.tool_name,
+            Event::ReleaseResolved {
+                tag_name,
+                asset_name: &asset.name,
+            },
+        );
+
+        Ok(asset)
+    }
 
-        self.pb_msg.set_message("Downloaded!");
-        SyncProgress::finish_progress(pb_downloading);
+    /// Download an asset from the release with the given tag and return a
+    /// path of the downloaded artefact
+    pub fn download(
+        &self,
+        tmp_dir: &Path,
+        tag_name: &str,
+        asset: &Asset,
+    ) -> Result<DownloadInfo, Box<dyn Error>> {
+        let (archive_path, asset_size) = self.download_asset(tmp_dir, asset)?;
 
-        Ok(download_path)
+        Ok(DownloadInfo {
+            archive_path,
+            asset_name: asset.name.clone(),
+            asset_size,
+            tag_name: tag_name.to_owned(),
+        })
     }
+}
 
-    /// Download an asset and return a path of the downloaded artefact
-    pub fn download(&self, tmp_dir: &Path) -> Result<DownloadInfo, Box<dyn Error>> {
-        self.pb_msg.set_message("Fetching info...");
+/// Errors that happen while saving the response body of a download to disk
+#[derive(Debug)]
+pub enum DownloadError {
+    /// Reading the response failed, e.g. the connection was dropped
+    Read(io::Error),
 
-        let release = self.download_release()?;
+    /// Writing the downloaded bytes to disk failed
+    Write(io::Error),
 
-        let asset = release
-            .assets
-            .iter()
-            .find(|&asset| asset.name.contains(self.asset_name));
-
-        match asset {
-            None => Err(format!("No asset matching name: {}", self.asset_name).into()),
-            Some(asset) => {
-                let archive_path = self.download_asset(tmp_dir, asset)?;
-
-                Ok(DownloadInfo {
-                    archive_path,
-                    asset_name: asset.name.clone(),
-                    tag_name: release.tag_name,
-                })
-            }
+    /// The number of downloaded bytes differs from the expected size
+    SizeMismatch {
+        expected: u64,
+        actual: u64,
+        source: &'static str,
+    },
+}
+
+impl DownloadError {
+    pub fn display(&self) -> String {
+        match self {
+            DownloadError::Read(e) => format!("[Download Error] Failed to read response: {}", e),
+            DownloadError::Write(e) => format!("[Download Error] Failed to write file: {}", e),
+            DownloadError::SizeMismatch {
+                expected,
+                actual,
+                source,
+            } => format!(
+                "[Download Error] Incomplete download: expected {} bytes ({}), got {} bytes",
+                expected, source, actual
+            ),
         }
     }
 }
 
-pub fn add_auth_header(req: ureq::Request) -> ureq::Request {
-    match env::var("GITHUB_TOKEN") {
-        Err(_) => req,
-        Ok(token) => req.set("Authorization", &format!("token {}", token)),
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display())
     }
 }
 
+impl Error for DownloadError {}
+
+/// Save the response body to a file and check that all expected bytes were
+/// received. 'asset_size' is checked only if the provider reports it. Calls
+/// 'on_progress' with the number of bytes downloaded so far. Returns the
+/// number of downloaded bytes.
+fn stream_to_file(
+    response: ureq::Response,
+    download_path: &Path,
+    asset_size: Option<u64>,
+    on_progress: &dyn Fn(u64),
+) -> Result<u64, DownloadError> {
+    let content_length: Option<u64> = response
+        .header("Content-Length")
+        .and_then(|len| len.parse().ok());
+
+    let mut stream = response.into_reader();
+    let mut destination = File::create(download_path).map_err(DownloadError::Write)?;
+
+    let mut downloaded: u64 = 0;
+    let mut buffer = [0; 4096];
+
+    loop {
+        let bytes_read = match stream.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(bytes_read) => bytes_read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(DownloadError::Read(e)),
+        };
+
+        destination
+            .write_all(&buffer[..bytes_read])
+            .map_err(DownloadError::Write)?;
+
+        downloaded += bytes_read as u64;
+        on_progress(downloaded);
+    }
+
+    destination.flush().map_err(DownloadError::Write)?;
+
+    if let Some(content_length) = content_length {
+        if downloaded != content_length {
+            return Err(DownloadError::SizeMismatch {
+                expected: content_length,
+                actual: downloaded,
+                source: "Content-Length",
+            });
+        }
+    }
+
+    if let Some(asset_size) = asset_size {
+        if downloaded != asset_size {
+            return Err(DownloadError::SizeMismatch {
+                expected: asset_size,
+                actual: downloaded,
+                source: "asset size",
+            });
+        }
+    }
+
+    Ok(downloaded)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::model::tool::ToolInfoTag;
+    use semver::VersionReq;
+    use std::fs;
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+    use tempdir::TempDir;
 
-    #[test]
-    fn release_url_with_latest_tag_is_correct() {
-        let downloader = Downloader {
+    use crate::sync::report::SilentReporter;
+
+    /// Start a local HTTP server that answers a single request with the given
+    /// raw response and closes the connection. Returns its base URL and the
+    /// receiver of the raw request headers.
+    fn mock_server(response: Vec<u8>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            // read the request headers before answering
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buffer[..n]),
+                }
+            }
+
+            stream.write_all(&response).unwrap();
+
+            // the test may not be interested in the request
+            let _ = sender.send(String::from_utf8_lossy(&request).into_owned());
+        });
+
+        (format!("http://{}", addr), receiver)
+    }
+
+    fn serve_once(response: Vec<u8>) -> String {
+        let (url, _) = mock_server(response);
+        format!("{}/asset", url)
+    }
+
+    fn json_response(body: &str) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .into_bytes()
+    }
+
+    fn downloader<'a>(provider: &'a Provider, tag: &'a ToolInfoTag) -> Downloader<'a> {
+        Downloader {
+            provider,
             owner: "OWNER",
             repo: "REPO",
-            asset_name: "ASSET_NAME",
-            version: &ToolInfoTag::Latest.to_str_version(),
-            pb_msg: &ProgressBar::hidden(),
-            sync_progress: &SyncProgress::new(vec!["tool".to_string()], vec!["latest".to_string()]),
+            asset_name: "linux",
+            tag,
+            prerelease: false,
+            tag_pattern: None,
+            tool_name: "tool",
+            reporter: &SilentReporter,
+        }
+    }
+
+    fn download_from(url: &str, asset_size: u64) -> (Result<u64, DownloadError>, TempDir) {
+        let tmp_dir = TempDir::new("tool-sync-test").unwrap();
+        let download_path = tmp_dir.path().join("asset");
+
+        let response = ureq::get(url).call().unwrap();
+        let result = stream_to_file(response, &download_path, Some(asset_size), &|_| {});
+
+        (result, tmp_dir)
+    }
+
+    #[test]
+    fn complete_download() {
+        let url = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello".to_vec());
+        let (result, tmp_dir) = download_from(&url, 5);
+
+        assert_eq!(result.unwrap(), 5);
+        assert_eq!(fs::read(tmp_dir.path().join("asset")).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn connection_closed_mid_body() {
+        let url = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\nhello".to_vec());
+        let (result, _tmp_dir) = download_from(&url, 100);
+
+        assert!(matches!(result, Err(DownloadError::Read(_))));
+    }
+
+    #[test]
+    fn body_without_content_length_is_shorter_than_asset() {
+        let url = serve_once(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nhello".to_vec());
+        let (result, _tmp_dir) = download_from(&url, 100);
+
+        assert!(matches!(
+            result,
+            Err(DownloadError::SizeMismatch {
+                expected: 100,
+                actual: 5,
+                source: "asset size",
+            })
+        ));
+    }
+
+    #[test]
+    fn release_from_enterprise_api() {
+        let (url, request) = mock_server(json_response(
+            r#"{"tag_name":"v1.0.0","assets":[{"id":1,"name":"tool-linux.tar.gz","size":5,"browser_download_url":"http://localhost/tool-linux.tar.gz"}]}"#,
+        ));
+        let provider = Provider::GitHub {
+            api_url: format!("{}/api/v3", url),
+            token: Some("SECRET".to_owned()),
+        };
+        let downloader = downloader(&provider, &ToolInfoTag::Latest);
+
+        let release = downloader.download_release().unwrap();
+        let asset = downloader.find_asset(&release).unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert_eq!(asset.name, "tool-linux.tar.gz");
+        assert_eq!(asset.size, Some(5));
+
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("GET /api/v3/repos/OWNER/REPO/releases/latest HTTP/1.1"));
+        assert!(request.contains("Authorization: token SECRET"));
+    }
+
+    #[test]
+    fn release_without_token() {
+        let (url, request) = mock_server(json_response(r#"{"tag_name":"v1.0.0","assets":[]}"#));
+        let provider = Provider::GitHub {
+            api_url: url,
+            token: None,
         };
+        let downloader = downloader(&provider, &ToolInfoTag::Latest);
 
+        assert_eq!(downloader.download_release().unwrap().tag_name, "v1.0.0");
+        assert!(!request.recv().unwrap().contains("Authorization"));
+    }
+
+    #[test]
+    fn release_from_gitlab() {
+        let (url, request) = mock_server(json_response(
+            r#"{"tag_name":"v1.0.0","assets":{"count":1,"links":[{"id":1,"name":"tool-linux.tar.gz","url":"http://localhost/tool-linux.tar.gz","link_type":"package"}]}}"#,
+        ));
+        let provider = Provider::GitLab {
+            api_url: format!("{}/api/v4", url),
+            token: Some("SECRET".to_owned()),
+        };
+        let tag = ToolInfoTag::Specific("v1.0.0".to_owned());
+        let downloader = downloader(&provider, &tag);
+
+        let release = downloader.download_release().unwrap();
+        let asset = downloader.find_asset(&release).unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
         assert_eq!(
-            downloader.release_url(),
-            "https://api.github.com/repos/OWNER/REPO/releases/latest"
+            asset.browser_download_url,
+            "http://localhost/tool-linux.tar.gz"
         );
+        assert_eq!(asset.size, None);
+
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("GET /api/v4/projects/OWNER%2FREPO/releases/v1.0.0 HTTP/1.1"));
+        assert!(request.contains("PRIVATE-TOKEN: SECRET"));
     }
 
     #[test]
-    fn release_url_with_specific_tag_is_correct() {
-        let downloader = Downloader {
-            owner: "OWNER",
-            repo: "REPO",
-            asset_name: "ASSET_NAME",
-            version: &ToolInfoTag::Specific("SPECIFIC_TAG".to_string()).to_str_version(),
-            pb_msg: &ProgressBar::hidden(),
-            sync_progress: &SyncProgress::new(vec!["tool".to_string()], vec!["latest".to_string()]),
+    fn release_from_gitea() {
+        let (url, request) = mock_server(json_response(
+            r#"{"id":7,"tag_name":"v1.0.0","name":"v1.0.0","draft":false,"prerelease":false,"assets":[{"id":3,"name":"tool-linux.tar.gz","size":5,"download_count":0,"uuid":"UUID","browser_download_url":"https://codeberg.org/OWNER/REPO/releases/download/v1.0.0/tool-linux.tar.gz"}]}"#,
+        ));
+        let provider = Provider::Gitea {
+            api_url: format!("{}/api/v1", url),
+            token: Some("SECRET".to_owned()),
         };
+        let downloader = downloader(&provider, &ToolInfoTag::Latest);
 
+        let release = downloader.download_release().unwrap();
+        let asset = downloader.find_asset(&release).unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert_eq!(asset.id, 3);
+        assert_eq!(asset.size, Some(5));
         assert_eq!(
-            downloader.release_url(),
-            "https://api.github.com/repos/OWNER/REPO/releases/tags/SPECIFIC_TAG"
+            asset.browser_download_url,
+            "https://codeberg.org/OWNER/REPO/releases/download/v1.0.0/tool-linux.tar.gz"
         );
+
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("GET /api/v1/repos/OWNER/REPO/releases/latest HTTP/1.1"));
+        assert!(request.contains("Authorization: token SECRET"));
+    }
+
+    #[test]
+    fn asset_from_gitlab_without_size() {
+        let (url, request) =
+            mock_server(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello".to_vec());
+        let provider = Provider::GitLab {
+            api_url: format!("{}/api/v4", url),
+            token: Some("SECRET".to_owned()),
+        };
+        let tag = ToolInfoTag::Latest;
+        let downloader = downloader(&provider, &tag);
+
+        let asset = Asset {
+            id: 1,
+            name: "tool-linux.tar.gz".to_owned(),
+            size: None,
+            browser_download_url: format!("{}/downloads/tool-linux.tar.gz", url),
+        };
+
+        let tmp_dir = TempDir::new("tool-sync-test").unwrap();
+        let download_info = downloader
+            .download(tmp_dir.path(), "v1.0.0", &asset)
+            .unwrap();
+
+        assert_eq!(download_info.asset_size, 5);
+        assert_eq!(fs::read(download_info.archive_path).unwrap(), b"hello");
+
+        // links on the same host get the token
+        assert!(request.recv().unwrap().contains("PRIVATE-TOKEN: SECRET"));
+    }
+
+    #[test]
+    fn asset_from_url() {
+        let (url, request) =
+            mock_server(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello".to_vec());
+        let provider = Provider::Url {
+            template: format!("{}/{{exe}}/{{version}}/{{exe}}_{{version}}_linux.zip", url),
+            exe_name: "tool".to_owned(),
+        };
+        let tag = ToolInfoTag::Specific("v1.0.0".to_owned());
+        let downloader = downloader(&provider, &tag);
+
+        let release = downloader.download_release().unwrap();
+        let asset = downloader.find_asset(&release).unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert_eq!(asset.name, "tool_1.0.0_linux.zip");
+
+        let tmp_dir = TempDir::new("tool-sync-test").unwrap();
+        let download_info = downloader
+            .download(tmp_dir.path(), &release.tag_name, asset)
+            .unwrap();
+
+        assert_eq!(download_info.asset_size, 5);
+        assert_eq!(fs::read(download_info.archive_path).unwrap(), b"hello");
+
+        // no API requests, only the asset itself
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("GET /tool/1.0.0/tool_1.0.0_linux.zip HTTP/1.1"));
+    }
+
+    #[test]
+    fn latest_release_matching_tag_pattern() {
+        let (url, request) = mock_server(json_response(
+            r#"[{"tag_name":"sdk-v1.1.0","prerelease":false,"draft":false,"published_at":"2022-09-03T00:00:00Z","assets":[]},{"tag_name":"cli-v2.4.0-rc.1","prerelease":true,"draft":false,"published_at":"2022-09-02T00:00:00Z","assets":[]},{"tag_name":"cli-v2.3.0","prerelease":false,"draft":false,"published_at":"2022-09-01T00:00:00Z","assets":[]}]"#,
+        ));
+        let provider = Provider::GitHub {
+            api_url: url,
+            token: None,
+        };
+        let downloader = Downloader {
+            tag_pattern: Some("^cli-"),
+            ..downloader(&provider, &ToolInfoTag::Latest)
+        };
+
+        let release = downloader.download_release().unwrap();
+        assert_eq!(release.tag_name, "cli-v2.3.0");
+
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("GET /repos/OWNER/REPO/releases?per_page=50&page=1 HTTP/1.1"));
+    }
+
+    #[test]
+    fn release_matching_version_requirement() {
+        let (url, request) = mock_server(json_response(
+            r#"[{"tag_name":"v0.24.0","assets":[]},{"tag_name":"v0.23.1","assets":[]},{"tag_name":"v0.22.0","assets":[]}]"#,
+        ));
+        let provider = Provider::Gitea {
+            api_url: url,
+            token: None,
+        };
+        let tag = ToolInfoTag::Requirement(VersionReq::parse(">=0.22, <0.24").unwrap());
+
+        let release = downloader(&provider, &tag).download_release().unwrap();
+        assert_eq!(release.tag_name, "v0.23.1");
+
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("GET /repos/OWNER/REPO/releases?limit=50&page=1 HTTP/1.1"));
     }
 }
@@ -1,32 +1,56 @@
-use indicatif::ProgressBar;
 use std::error::Error;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
 use tempdir::TempDir;
 
-#[cfg(target_family = "unix")]
-use std::os::unix::fs::PermissionsExt;
-
-use crate::config::schema::ConfigAsset;
+use crate::config::schema::{Config, ConfigAsset};
 use crate::err;
 use crate::model::asset_name::mk_exe_name;
+use crate::model::release::Asset;
 use crate::model::tool::{Tool, ToolInfo};
 
 use super::archive::Archive;
+use super::checksum::{find_checksum_asset, parse_checksum, sha256_file, verify_sha256};
 use super::configure::configure_tool;
-use super::download::Downloader;
-use super::progress::SyncProgress;
+use super::download::{DownloadInfo, Downloader};
+use super::lock::LockFile;
+use super::outcome::{fail, ErrorKind, InstallError, InstallOutcome};
+use super::provider::ApiConfig;
+use super::replace;
+use super::report::{Event, InstalledFile, Reporter};
+use super::state::{load_state, now_timestamp, InstallRecord, InstallState, InstalledExe};
+use super::versions::{activate, previous_versions, version_directory};
 
 pub struct Installer {
     store_directory: PathBuf,
+    completions_directory: Option<PathBuf>,
+    man_directory: Option<PathBuf>,
+    keep_versions: usize,
     tmp_dir: TempDir,
-    sync_progress: SyncProgress,
+    reporter: Box<dyn Reporter>,
+    api_config: ApiConfig,
+    state: Mutex<InstallState>,
+    force: bool,
+    lock_file: Option<LockFile>,
 }
 
 impl Installer {
     /// This functions panics when it can't create a temporary directory
-    /// (e.g. not enough disk space?)
-    pub fn mk(store_directory: PathBuf, sync_progress: SyncProgress) -> Installer {
+    /// (e.g. not enough disk space?), can't read the install state or
+    /// 'store_directory' from the config doesn't exist
+    ///
+    /// When 'force' is set, tools are reinstalled even if they're up to date.
+    /// When 'lock_file' is given, exactly the locked assets are installed.
+    pub fn mk(
+        config: &Config,
+        reporter: Box<dyn Reporter>,
+        force: bool,
+        lock_file: Option<LockFile>,
+    ) -> Installer {
+        let store_directory = config.ensure_store_directory();
+        let state = load_state(&store_directory);
+
         let tmp_dir = TempDir::new("tool-sync");
         match tmp_dir {
             Err(e) => {
@@ -34,97 +58,530 @@ impl Installer {
             }
             Ok(tmp_dir) => Installer {
                 store_directory,
+                completions_directory: config.completions_directory(),
+                man_directory: config.man_directory(),
+                keep_versions: config.keep_versions(),
                 tmp_dir,
-                sync_progress,
+                reporter,
+                api_config: ApiConfig::from_config(config),
+                state: Mutex::new(state),
+                force,
+                lock_file,
             },
         }
     }
 
-    pub fn install(&self, tool_name: &str, config_asset: &ConfigAsset) {
-        let tag: String = config_asset.tag.clone().unwrap_or_else(|| "latest".into());
-        let pb_msg = self.sync_progress.create_message_bar(tool_name, &tag);
+    /// Install a single tool and return its outcome
+    pub fn install(&self, tool_name: &str, config_asset: &ConfigAsset) -> InstallOutcome {
+        let tag: String = config_asset.tag_label();
+        self.reporter
+            .report(tool_name, Event::Started { tag: &tag });
 
-        match configure_tool(tool_name, config_asset) {
-            Tool::Known(tool_info) => match self.sync_single_tool(&tool_info, &pb_msg) {
-                Ok(tag_name) => {
-                    self.sync_progress.success(pb_msg, tool_name, &tag_name);
-                }
-                Err(e) => {
-                    self.sync_progress
-                        .failure(pb_msg, tool_name, &tag, format!("[error] {}", e));
-                }
-            },
+        let outcome = match configure_tool(tool_name, config_asset) {
+            Tool::Known(tool_info) => self
+                .sync_single_tool(tool_name, &tool_info)
+                .unwrap_or_else(InstallOutcome::Failed),
             Tool::Error(e) => {
-                self.sync_progress
-                    .failure(pb_msg, tool_name, &tag, e.display());
+                InstallOutcome::Failed(InstallError::new(ErrorKind::Config, e.display()))
+            }
+        };
+
+        match &outcome {
+            InstallOutcome::Installed(tag_name) => {
+                let store_directory = &self.store_directory;
+                let state = self.lock_state();
+                let executables = state
+                    .tools
+                    .get(tool_name)
+                    .map(|record| record.executables.as_slice())
+                    .unwrap_or_default();
+                let paths: Vec<PathBuf> = executables
+                    .iter()
+                    .map(|executable| store_directory.join(&executable.name))
+                    .collect();
+
+                self.reporter.report(
+                    tool_name,
+                    Event::Installed {
+                        tag_name,
+                        executables: paths
+                            .iter()
+                            .zip(executables.iter())
+                            .map(|(path, executable)| InstalledFile {
+                                path,
+                                sha256: &executable.sha256,
+                            })
+                            .collect(),
+                    },
+                );
             }
+            InstallOutcome::UpToDate(tag_name) => self
+                .reporter
+                .report(tool_name, Event::UpToDate { tag_name }),
+            InstallOutcome::Skipped(reason) => self
+                .reporter
+                .report(tool_name, Event::Skipped { tag: &tag, reason }),
+            InstallOutcome::Failed(e) => self.reporter.report(
+                tool_name,
+                Event::Failed {
+                    tag: &tag,
+                    kind: e.kind,
+                    message: &e.message,
+                },
+            ),
         }
+
+        outcome
     }
 
     fn sync_single_tool(
         &self,
+        tool_name: &str,
         tool_info: &ToolInfo,
-        pb_msg: &ProgressBar,
-    ) -> Result<String, Box<dyn Error>> {
+    ) -> Result<InstallOutcome, InstallError> {
         match tool_info.asset_name.get_name_by_os() {
-            None => Err(
-                "Don't know the asset name for this OS: specify it explicitly in the config".into(),
-            ),
+            None => Ok(InstallOutcome::Skipped(
+                "No asset name for this OS: specify it in the config to install".into(),
+            )),
             Some(asset_name) => {
+                let provider = self.api_config.provider(tool_info);
+
                 let downloader = Downloader {
+                    provider: &provider,
                     owner: &tool_info.owner,
                     repo: &tool_info.repo,
-                    version: &tool_info.tag.to_str_version(),
-                    sync_progress: &self.sync_progress,
-                    pb_msg,
+                    tag: &tool_info.tag,
+                    prerelease: tool_info.prerelease,
+                    tag_pattern: tool_info.tag_pattern.as_deref(),
+                    tool_name,
+                    reporter: self.reporter.as_ref(),
                     asset_name,
                 };
 
-                let download_info = downloader.download(self.tmp_dir.path())?;
+                let (tag_name, asset, checksum_asset, locked_sha256) = match &self.lock_file {
+                    // locked assets are downloaded directly without resolving the release
+                    Some(lock_file) => {
+                        let locked_asset = lock_file
+                            .locked_asset(tool_name, tool_info)
+                            .map_err(fail(ErrorKind::Config))?;
+                        (
+                            locked_asset.tag_name.clone(),
+                            locked_asset.to_asset(),
+                            None,
+                            Some(locked_asset.sha256.clone()),
+                        )
+                    }
+                    None => {
+                        let release = downloader
+                            .download_release()
+                            .map_err(fail(ErrorKind::Network))?;
+                        let asset = downloader
+                            .find_asset(&release)
+                            .map_err(fail(ErrorKind::AssetNotFound))?
+                            .clone();
+                        let checksum_asset =
+                            find_checksum_asset(&release.assets, &asset.name).cloned();
+                        (release.tag_name, asset, checksum_asset, None)
+                    }
+                };
+
+                if !self.force && self.is_up_to_date(tool_name, &tag_name, &asset.name) {
+                    return Ok(InstallOutcome::UpToDate(tag_name));
+                }
+
+                // each tool gets its own directory, so archives with the same
+                // layout don't overwrite each other
+                let tmp_dir = self.tmp_dir.path().join(tool_name);
+                fs::create_dir_all(&tmp_dir).map_err(fail(ErrorKind::Io))?;
+
+                let download_info = downloader
+                    .download(&tmp_dir, &tag_name, &asset)
+                    .map_err(fail(ErrorKind::Network))?;
+
+                self.verify_checksums(
+                    &downloader,
+                    &tmp_dir,
+                    tool_info,
+                    &download_info,
+                    locked_sha256.as_deref(),
+                    checksum_asset.as_ref(),
+                )
+                .map_err(fail(ErrorKind::Checksum))?;
 
                 let archive = Archive::from(
                     &download_info.archive_path,
-                    self.tmp_dir.path(),
+                    &tmp_dir,
                     &tool_info.exe_name,
                     &download_info.asset_name,
+                    tool_info.asset_type.as_deref(),
+                    tool_info.exe_path.as_deref(),
                 );
 
                 match archive {
-                    None => {
-                        Err(format!("Unsupported asset type: {}", download_info.asset_name).into())
-                    }
+                    None => Err(InstallError::new(
+                        ErrorKind::Unpack,
+                        format!(
+                            "Unsupported asset type: {} (specify 'asset_type' in the config)",
+                            download_info.asset_name
+                        ),
+                    )),
                     Some(archive) => match archive.unpack() {
-                        Err(unpack_err) => Err(unpack_err.display().into()),
-                        Ok(tool_path) => {
-                            copy_file(tool_path, &self.store_directory, &tool_info.exe_name)?;
-                            Ok(download_info.tag_name)
+                        Err(unpack_err) => {
+                            Err(InstallError::new(ErrorKind::Unpack, unpack_err.display()))
+                        }
+                        Ok(executables) => {
+                            // tools can share executables, so only one tool at
+                            // a time changes files in 'store_directory'
+                            let mut state = self.lock_state();
+
+                            self.install_files(
+                                &mut state,
+                                tool_name,
+                                tool_info,
+                                &archive,
+                                executables,
+                                &download_info,
+                            )
+                            .map_err(fail(ErrorKind::Io))?;
+
+                            Ok(InstallOutcome::Installed(download_info.tag_name))
                         }
                     },
                 }
             }
         }
     }
+
+    /// Copy executables, completions and man pages from the unpacked archive
+    /// to their directories and record the installation
+    fn install_files(
+        &self,
+        state: &mut InstallState,
+        tool_name: &str,
+        tool_info: &ToolInfo,
+        archive: &Archive,
+        executables: Vec<(PathBuf, &str)>,
+        download_info: &DownloadInfo,
+    ) -> Result<(), Box<dyn Error>> {
+        let version_directory =
+            version_directory(&self.store_directory, tool_name, &download_info.tag_name);
+        let version_paths = install_executables(executables, &version_directory)?;
+        let install_paths = activate(&self.store_directory, &version_paths)?;
+
+        let extra_files = self.install_extra_files(archive, tool_info)?;
+
+        self.record_install(
+            state,
+            tool_name,
+            tool_info,
+            download_info,
+            &install_paths,
+            extra_files,
+        )
+    }
+
+    /// Check the downloaded asset against all known hashes: from the lock
+    /// file, from the config and from the checksum asset of the release
+    fn verify_checksums(
+        &self,
+        downloader: &Downloader,
+        tmp_dir: &Path,
+        tool_info: &ToolInfo,
+        download_info: &DownloadInfo,
+        locked_sha256: Option<&str>,
+        checksum_asset: Option<&Asset>,
+    ) -> Result<(), Box<dyn Error>> {
+        let archive_path = &download_info.archive_path;
+        let asset_name = &download_info.asset_name;
+
+        if let Some(locked_sha256) = locked_sha256 {
+            verify_sha256(archive_path, asset_name, locked_sha256, "lock file")?;
+        }
+
+        if let Some(config_sha256) = &tool_info.sha256 {
+            verify_sha256(archive_path, asset_name, config_sha256, "config")?;
+        }
+
+        if let Some(checksum_asset) = checksum_asset {
+            let checksum_info =
+                downloader.download(tmp_dir, &download_info.tag_name, checksum_asset)?;
+            let checksums = fs::read_to_string(&checksum_info.archive_path)?;
+
+            match parse_checksum(&checksums, &checksum_asset.name, asset_name) {
+                Some(release_sha256) => verify_sha256(
+                    archive_path,
+                    asset_name,
+                    &release_sha256,
+                    &checksum_asset.name,
+                )?,
+                None => self.reporter.report(
+                    downloader.tool_name,
+                    Event::ChecksumSkipped {
+                        checksum_asset: &checksum_asset.name,
+                    },
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy shell completions and man pages from the unpacked archive to
+    /// their directories and return paths to the installed files. Files are
+    /// installed only when the corresponding directory is configured.
+    fn install_extra_files(
+        &self,
+        archive: &Archive,
+        tool_info: &ToolInfo,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut extra_files = Vec::new();
+
+        if let (Some(completions_directory), Some(completions)) =
+            (&self.completions_directory, &tool_info.completions)
+        {
+            let files = archive
+                .find_files("completions", completions)
+                .map_err(|e| e.display())?;
+
+            for path in files {
+                extra_files.push(copy_to_directory(&path, completions_directory)?);
+            }
+        }
+
+        if let (Some(man_directory), Some(man_pages)) = (&self.man_directory, &tool_info.man_pages)
+        {
+            let files = archive
+                .find_files("man_pages", man_pages)
+                .map_err(|e| e.display())?;
+
+            for path in files {
+                extra_files.push(copy_to_directory(
+                    &path,
+                    &man_section_directory(man_directory, &path),
+                )?);
+            }
+        }
+
+        Ok(extra_files)
+    }
+
+    fn is_up_to_date(&self, tool_name: &str, tag_name: &str, asset_name: &str) -> bool {
+        self.lock_state()
+            .is_up_to_date(&self.store_directory, tool_name, tag_name, asset_name)
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, InstallState> {
+        // the lock is poisoned only when another installation panicked
+        self.state.lock().unwrap()
+    }
+
+    /// Save info about the freshly installed tool to the state file
+    fn record_install(
+        &self,
+        state: &mut InstallState,
+        tool_name: &str,
+        tool_info: &ToolInfo,
+        download_info: &DownloadInfo,
+        install_paths: &[PathBuf],
+        extra_files: Vec<PathBuf>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut executables = Vec::new();
+
+        for install_path in install_paths {
+            executables.push(InstalledExe {
+                // unwrap is safe here because the path always ends with the exe name
+                name: install_path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+                sha256: sha256_file(install_path)?,
+            });
+        }
+
+        let previous_versions = previous_versions(
+            &self.store_directory,
+            tool_name,
+            state.tools.get(tool_name),
+            &download_info.tag_name,
+            self.keep_versions,
+        );
+
+        let record = InstallRecord {
+            owner: tool_info.owner.clone(),
+            repo: tool_info.repo.clone(),
+            tag_name: download_info.tag_name.clone(),
+            asset_name: download_info.asset_name.clone(),
+            asset_size: download_info.asset_size,
+            installed_at: now_timestamp(),
+            extra_files,
+            executables,
+            previous_versions,
+        };
+
+        state.tools.insert(tool_name.to_owned(), record);
+        state
+            .save(&self.store_directory)
+            .map_err(|e| format!("Error saving install state: {}", e.display()).into())
+    }
 }
 
-fn copy_file(tool_path: PathBuf, store_directory: &PathBuf, exe_name: &str) -> std::io::Result<()> {
-    let exe_name = mk_exe_name(exe_name);
+/// Copy all executables to a fresh version directory and return paths to the
+/// copied files. Either all executables are copied or none of them.
+///
+/// The same tag can be reinstalled with different executables, so files are
+/// copied to a staging directory first. It replaces the version directory
+/// only when all files are there, and the old directory is kept otherwise.
+fn install_executables(
+    executables: Vec<(PathBuf, &str)>,
+    version_directory: &Path,
+) -> std::io::Result<Vec<PathBuf>> {
+    let staging_directory = sibling_directory(version_directory, ".tool-sync.tmp");
+    if staging_directory.exists() {
+        fs::remove_dir_all(&staging_directory)?;
+    }
+    fs::create_dir_all(&staging_directory)?;
+
+    let files = executables
+        .into_iter()
+        .map(|(tool_path, exe_name)| (tool_path, staging_directory.join(mk_exe_name(exe_name))))
+        .collect();
+
+    let copied = replace::stage_all(files).and_then(replace::replace_all);
+    let moved = copied.and_then(|paths| {
+        replace_directory(&staging_directory, version_directory)?;
+        Ok(paths)
+    });
+
+    match moved {
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging_directory);
+            Err(e)
+        }
+        Ok(paths) => Ok(paths
+            .iter()
+            // unwrap is safe here because copied files always end with the exe name
+            .map(|path| version_directory.join(path.file_name().unwrap()))
+            .collect()),
+    }
+}
 
-    let mut install_path = PathBuf::new();
-    install_path.push(store_directory);
-    install_path.push(exe_name);
+/// Move the directory to 'target', replacing the existing one. The old
+/// directory is restored if the move fails.
+fn replace_directory(directory: &Path, target: &Path) -> std::io::Result<()> {
+    if !target.exists() {
+        return fs::rename(directory, target);
+    }
 
-    // Copy file from the downloaded unpacked archive to 'store_directory'
-    fs::copy(tool_path, &install_path)?;
+    let backup = sibling_directory(target, ".old");
+    if backup.exists() {
+        fs::remove_dir_all(&backup)?;
+    }
+    fs::rename(target, &backup)?;
 
-    set_executable_permissions(&install_path);
+    if let Err(e) = fs::rename(directory, target) {
+        let _ = fs::rename(&backup, target);
+        return Err(e);
+    }
 
+    // the new files are in place, so a leftover backup is only wasted space
+    let _ = fs::remove_dir_all(&backup);
     Ok(())
 }
 
-#[cfg(target_family = "windows")]
-fn set_executable_permissions(_exe_path: &PathBuf) {}
+/// Hidden directory next to 'directory' with the given suffix, e.g.
+/// '.14.0.0.old' for '14.0.0'
+fn sibling_directory(directory: &Path, suffix: &str) -> PathBuf {
+    // unwrap is safe here because version directories always end with the tag
+    let name = directory.file_name().unwrap().to_string_lossy();
+    directory.with_file_name(format!(".{}{}", name, suffix))
+}
+
+/// Copy the file to the directory under the same name and return the path to
+/// the copied file. The directory is created if it doesn't exist.
+fn copy_to_directory(path: &Path, directory: &Path) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(directory)?;
+
+    // unwrap is safe here because all found files have names
+    let install_path = directory.join(path.file_name().unwrap());
+    fs::copy(path, &install_path)?;
+
+    Ok(install_path)
+}
+
+/// Man pages are installed to the 'man<section>' subdirectory, where the
+/// section is the extension of the man page, e.g. 'man1' for 'rg.1'
+fn man_section_directory(man_directory: &Path, man_page: &Path) -> PathBuf {
+    match man_page
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some(section) if section.starts_with(|c: char| c.is_ascii_digit()) => {
+            man_directory.join(format!("man{}", section))
+        }
+        _ => man_directory.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
 
-#[cfg(target_family = "unix")]
-fn set_executable_permissions(exe_path: &PathBuf) {
-    fs::set_permissions(exe_path, fs::Permissions::from_mode(0o755)).unwrap();
+    #[test]
+    fn reinstalled_version_replaces_directory() {
+        let src_dir = TempDir::new("tool-sync-test").unwrap();
+        let versions_dir = TempDir::new("tool-sync-test").unwrap();
+
+        let version_directory = versions_dir.path().join("13.0.0");
+        fs::create_dir_all(&version_directory).unwrap();
+        fs::write(version_directory.join(mk_exe_name("old")), "old").unwrap();
+
+        let new_exe = src_dir.path().join("rg");
+        fs::write(&new_exe, "new").unwrap();
+
+        let paths = install_executables(vec![(new_exe, "rg")], &version_directory).unwrap();
+
+        assert_eq!(paths, vec![version_directory.join(mk_exe_name("rg"))]);
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "new");
+        assert!(!version_directory.join(mk_exe_name("old")).exists());
+        assert_eq!(fs::read_dir(versions_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn failed_reinstall_keeps_version_directory() {
+        let src_dir = TempDir::new("tool-sync-test").unwrap();
+        let versions_dir = TempDir::new("tool-sync-test").unwrap();
+
+        let version_directory = versions_dir.path().join("13.0.0");
+        fs::create_dir_all(&version_directory).unwrap();
+        fs::write(version_directory.join(mk_exe_name("rg")), "old").unwrap();
+
+        let missing_exe = src_dir.path().join("rg");
+
+        assert!(install_executables(vec![(missing_exe, "rg")], &version_directory).is_err());
+        assert_eq!(
+            fs::read_to_string(version_directory.join(mk_exe_name("rg"))).unwrap(),
+            "old"
+        );
+        assert_eq!(fs::read_dir(versions_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn man_pages_go_to_section_directories() {
+        let man_directory = Path::new("/usr/local/share/man");
+
+        assert_eq!(
+            man_section_directory(man_directory, Path::new("/tmp/rg.1")),
+            man_directory.join("man1")
+        );
+        assert_eq!(
+            man_section_directory(man_directory, Path::new("/tmp/exa_colors.5")),
+            man_directory.join("man5")
+        );
+        assert_eq!(
+            man_section_directory(man_directory, Path::new("/tmp/README.md")),
+            man_directory.to_path_buf()
+        );
+    }
 }
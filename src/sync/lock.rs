@@ -0,0 +1,559 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tempdir::TempDir;
+
+use crate::config::cli::Output;
+use crate::config::schema::Config;
+use crate::err;
+use crate::model::release::Asset;
+use crate::model::tool::{Tool, ToolInfo, ToolInfoTag};
+use crate::model::version::{matches_requirement, tag_version};
+
+use super::checksum::{sha256_file, verify_sha256};
+use super::configure::configure_tool;
+use super::download::Downloader;
+use super::no_tools_message;
+use super::outcome::{fail, ErrorKind, InstallError};
+use super::provider::{ApiConfig, Provider};
+use super::report::{config_reporter, Event, Reporter};
+use super::state::write_atomically;
+
+/// Name of the lock file, stored next to the configuration file
+pub const LOCK_FILE_NAME: &str = ".tool.lock";
+
+/// Systems with their own asset names in the config. Assets are locked for
+/// each of them, so the same lock file works everywhere.
+const LOCKED_OS: [&str; 3] = ["linux", "macos", "windows"];
+
+/// Concrete assets for all tools from the config, pinned by 'tool lock'
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct LockFile {
+    /// Locked assets for each tool, keyed by tool name and then by OS name
+    #[serde(default)]
+    pub tools: BTreeMap<String, BTreeMap<String, LockedAsset>>,
+}
+
+/// A single asset pinned for one tool on one OS
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockedAsset {
+    /// GitHub repository author
+    pub owner: String,
+
+    /// GitHub repository name
+    pub repo: String,
+
+    /// Resolved release tag
+    pub tag_name: String,
+
+    /// GitHub id of the asset
+    pub asset_id: u32,
+
+    /// Full name of the asset
+    pub asset_name: String,
+
+    /// Size of the asset in bytes
+    pub asset_size: u64,
+
+    /// URL to download the asset from a browser
+    pub download_url: String,
+
+    /// sha256 of the asset
+    pub sha256: String,
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    IO(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl LockError {
+    pub fn display(&self) -> String {
+        match self {
+            LockError::IO(e) => format!("[IO Error] {}", e),
+            LockError::Parse(e) => format!("[Parsing Error] {}", e),
+            LockError::Serialize(e) => format!("[Serialize Error] {}", e),
+        }
+    }
+}
+
+impl LockFile {
+    /// Read the lock file. A missing file means that nothing was locked yet.
+    pub fn load(lock_path: &Path) -> Result<LockFile, LockError> {
+        match fs::read_to_string(lock_path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(LockFile::default()),
+            Err(e) => Err(LockError::IO(e)),
+            Ok(contents) => toml::from_str(&contents).map_err(LockError::Parse),
+        }
+    }
+
+    /// Write the lock file atomically
+    pub fn save(&self, lock_path: &Path) -> Result<(), LockError> {
+        let contents = toml::to_string(self).map_err(LockError::Serialize)?;
+        write_atomically(lock_path, &contents).map_err(LockError::IO)
+    }
+
+    /// Find the locked asset of a tool for the current OS and check that it
+    /// still matches the tool configuration
+    pub fn locked_asset(
+        &self,
+        tool_name: &str,
+        tool_info: &ToolInfo,
+    ) -> Result<&LockedAsset, Box<dyn Error>> {
+        let locked_asset = self
+            .tools
+            .get(tool_name)
+            .and_then(|assets| assets.get(env::consts::OS))
+            .ok_or_else(|| {
+                format!(
+                    "No locked asset for OS '{}': run 'tool lock' to update the lock file",
+                    env::consts::OS
+                )
+            })?;
+
+        let is_same_tag = match &tool_info.tag {
+            ToolInfoTag::Latest => true,
+            ToolInfoTag::Specific(tag) => &locked_asset.tag_name == tag,
+            ToolInfoTag::Requirement(requirement) => tag_version(&locked_asset.tag_name)
+                .is_some_and(|version| {
+                    matches_requirement(requirement, &version, tool_info.prerelease)
+                }),
+        };
+
+        if locked_asset.owner != tool_info.owner
+            || locked_asset.repo != tool_info.repo
+            || !is_same_tag
+        {
+            return Err("Lock file is out of date: run 'tool lock' to update it".into());
+        }
+
+        Ok(locked_asset)
+    }
+}
+
+impl LockedAsset {
+    /// Asset info to download the locked asset directly, without resolving
+    /// the release
+    pub fn to_asset(&self) -> Asset {
+        Asset {
+            id: self.asset_id,
+            name: self.asset_name.clone(),
+            size: Some(self.asset_size),
+            browser_download_url: self.download_url.clone(),
+        }
+    }
+}
+
+/// Path to the lock file next to the given configuration file
+pub fn lock_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(LOCK_FILE_NAME)
+}
+
+/// Read the lock file next to the configuration file and exit with error if it
+/// can't be read
+pub fn load_lock_file(config_path: &Path) -> LockFile {
+    let lock_path = lock_path(config_path);
+
+    match LockFile::load(&lock_path) {
+        Err(e) => {
+            err::abort_with(&format!(
+                "Error reading lock file at path {}: {}",
+                lock_path.display(),
+                e.display()
+            ));
+        }
+        Ok(lock_file) => lock_file,
+    }
+}
+
+/// Same as 'load_lock_file' but also exits with error if the lock file doesn't
+/// exist
+pub fn require_lock_file(config_path: &Path) -> LockFile {
+    let lock_path = lock_path(config_path);
+
+    if !lock_path.is_file() {
+        err::abort_with(&format!(
+            "Lock file doesn't exist at path {}: run 'tool lock' to create it",
+            lock_path.display()
+        ));
+    }
+
+    load_lock_file(config_path)
+}
+
+/// Resolve every tool in the config to a concrete asset for each OS with an
+/// asset name and write the result to the lock file next to the config
+pub fn lock(config: Config, output: Output, config_path: &Path) {
+    if config.tools.is_empty() {
+        no_tools_message();
+        return;
+    }
+
+    let mut lock_file = load_lock_file(config_path);
+
+    // forget about tools that were removed from the config
+    lock_file
+        .tools
+        .retain(|tool_name, _| config.tools.contains_key(tool_name));
+
+    let tmp_dir = match TempDir::new("tool-sync") {
+        Err(e) => {
+            err::abort_suggest_issue(&format!("Error creating temporary directory: {}", e));
+        }
+        Ok(tmp_dir) => tmp_dir,
+    };
+
+    let reporter = config_reporter(output, &config);
+    let api_config = ApiConfig::from_config(&config);
+
+    let mut has_errors = false;
+
+    for (tool_name, config_asset) in config.tools.iter() {
+        let tag = config_asset.tag_label();
+        reporter.report(tool_name, Event::Started { tag: &tag });
+
+        let locked_assets = match configure_tool(tool_name, config_asset) {
+            Tool::Known(tool_info) => lock_tool(
+                reporter.as_ref(),
+                &api_config.provider(&tool_info),
+                tool_name,
+                tmp_dir.path(),
+                &tool_info,
+            ),
+            Tool::Error(e) => Err(InstallError::new(ErrorKind::Config, e.display())),
+        };
+
+        match locked_assets {
+            Err(e) => {
+                has_errors = true;
+                reporter.report(
+                    tool_name,
+                    Event::Failed {
+                        tag: &tag,
+                        kind: e.kind,
+                        message: &e.message,
+                    },
+                );
+            }
+            Ok(locked_assets) => {
+                let locked_asset = &locked_assets[env::consts::OS];
+                reporter.report(
+                    tool_name,
+                    Event::Locked {
+                        tag_name: &locked_asset.tag_name,
+                        sha256: &locked_asset.sha256,
+                    },
+                );
+                lock_file.tools.insert(tool_name.clone(), locked_assets);
+            }
+        }
+    }
+
+    if has_errors {
+        err::abort_with("Some tools can't be locked: the lock file is not updated");
+    }
+
+    let lock_path = lock_path(config_path);
+    if let Err(e) = lock_file.save(&lock_path) {
+        err::abort_with(&format!(
+            "Error writing lock file at path {}: {}",
+            lock_path.display(),
+            e.display()
+        ));
+    }
+}
+
+/// Resolve the release of a single tool once and download its asset for each
+/// OS to compute the hashes. Assets are keyed by OS name, and the current OS
+/// is always among them.
+fn lock_tool(
+    reporter: &dyn Reporter,
+    provider: &Provider,
+    tool_name: &str,
+    tmp_dir: &Path,
+    tool_info: &ToolInfo,
+) -> Result<BTreeMap<String, LockedAsset>, InstallError> {
+    let asset_name = tool_info.asset_name.get_name_by_os().ok_or_else(|| {
+        InstallError::new(
+            ErrorKind::Config,
+            "Don't know the asset name for this OS: specify it explicitly in the config".into(),
+        )
+    })?;
+
+    let downloader = Downloader {
+        provider,
+        owner: &tool_info.owner,
+        repo: &tool_info.repo,
+        tag: &tool_info.tag,
+        prerelease: tool_info.prerelease,
+        tag_pattern: tool_info.tag_pattern.as_deref(),
+        tool_name,
+        reporter,
+        asset_name,
+    };
+
+    let release = downloader
+        .download_release()
+        .map_err(fail(ErrorKind::Network))?;
+
+    let mut locked_os = LOCKED_OS.to_vec();
+    if !locked_os.contains(&env::consts::OS) {
+        locked_os.push(env::consts::OS);
+    }
+
+    let mut locked_assets = BTreeMap::new();
+
+    for os in locked_os {
+        let asset_name = match tool_info.asset_name.get_name(os) {
+            Some(asset_name) => asset_name,
+            None => continue,
+        };
+        let downloader = Downloader {
+            asset_name,
+            ..downloader
+        };
+
+        let asset = downloader
+            .find_os_asset(&release, os)
+            .map_err(fail(ErrorKind::AssetNotFound))?;
+        let download_info = downloader
+            .download(tmp_dir, &release.tag_name, &asset)
+            .map_err(fail(ErrorKind::Network))?;
+
+        // the hash in the config is for the asset of the current OS
+        let config_sha256 = tool_info.sha256.as_ref().filter(|_| os == env::consts::OS);
+        if let Some(config_sha256) = config_sha256 {
+            verify_sha256(
+                &download_info.archive_path,
+                &asset.name,
+                config_sha256,
+                "config",
+            )
+            .map_err(fail(ErrorKind::Checksum))?;
+        }
+
+        let sha256 = sha256_file(&download_info.archive_path).map_err(fail(ErrorKind::Io))?;
+
+        locked_assets.insert(
+            os.to_owned(),
+            LockedAsset {
+                owner: tool_info.owner.clone(),
+                repo: tool_info.repo.clone(),
+                tag_name: release.tag_name.clone(),
+                asset_id: asset.id,
+                asset_name: asset.name,
+                asset_size: download_info.asset_size,
+                download_url: asset.browser_download_url,
+                sha256,
+            },
+        );
+    }
+
+    Ok(locked_assets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::asset_name::AssetName;
+    use crate::model::exe_name::ExeName;
+    use crate::sync::report::SilentReporter;
+    use semver::VersionReq;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Start a local HTTP server that answers the requests in order with the
+    /// bodies returned for its base URL
+    fn mock_server(bodies: impl FnOnce(&str) -> Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let bodies = bodies(&url);
+
+        thread::spawn(move || {
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        url
+    }
+
+    fn ripgrep_info(tag: ToolInfoTag) -> ToolInfo {
+        ToolInfo {
+            owner: "BurntSushi".to_string(),
+            repo: "ripgrep".to_string(),
+            exe_name: ExeName::from("rg"),
+            asset_name: AssetName::default(),
+            tag,
+            ..ToolInfo::default()
+        }
+    }
+
+    fn ripgrep_lock_file() -> LockFile {
+        let locked_asset = LockedAsset {
+            owner: "BurntSushi".to_string(),
+            repo: "ripgrep".to_string(),
+            tag_name: "13.0.0".to_string(),
+            asset_id: 42,
+            asset_name: "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz".to_string(),
+            asset_size: 1_942_365,
+            download_url: "https://github.com/BurntSushi/ripgrep/releases/download/13.0.0/ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz".to_string(),
+            sha256: "abcdef".to_string(),
+        };
+
+        LockFile {
+            tools: BTreeMap::from([(
+                "ripgrep".to_string(),
+                BTreeMap::from([(env::consts::OS.to_string(), locked_asset)]),
+            )]),
+        }
+    }
+
+    #[test]
+    fn all_os_locked_from_same_release() {
+        let api_url = mock_server(|url| {
+            let asset = |id: u32, name: &str| {
+                format!(
+                    r#"{{"id":{},"name":"{}","size":5,"browser_download_url":"{}/{}"}}"#,
+                    id, name, url, name
+                )
+            };
+            let release = format!(
+                r#"{{"tag_name":"v1.1.0","assets":[{},{},{}]}}"#,
+                asset(1, "tool-linux.tar.gz"),
+                asset(2, "tool-macos.tar.gz"),
+                asset(3, "tool-windows.zip")
+            );
+
+            // the release is fetched once, then assets are downloaded in the
+            // order of systems
+            let mut bodies = vec![
+                release,
+                "linux".to_owned(),
+                "macos".to_owned(),
+                "win64".to_owned(),
+            ];
+            if cfg!(not(any(
+                target_os = "linux",
+                target_os = "macos",
+                target_os = "windows"
+            ))) {
+                bodies.push("linux".to_owned());
+            }
+            bodies
+        });
+        let provider = Provider::Gitea {
+            api_url,
+            token: None,
+        };
+        let tool_info = ToolInfo {
+            owner: "OWNER".to_string(),
+            repo: "REPO".to_string(),
+            asset_name: AssetName {
+                linux: Some("linux".to_string()),
+                macos: Some("macos".to_string()),
+                windows: Some("windows".to_string()),
+            },
+            ..ToolInfo::default()
+        };
+        let tmp_dir = TempDir::new("tool-sync-test").unwrap();
+
+        let locked_assets = lock_tool(
+            &SilentReporter,
+            &provider,
+            "tool",
+            tmp_dir.path(),
+            &tool_info,
+        )
+        .unwrap();
+
+        for os in LOCKED_OS {
+            assert_eq!(locked_assets[os].tag_name, "v1.1.0");
+        }
+        assert_eq!(locked_assets["macos"].asset_name, "tool-macos.tar.gz");
+        assert_eq!(locked_assets["windows"].asset_id, 3);
+        assert_ne!(locked_assets["linux"].sha256, locked_assets["macos"].sha256);
+        assert!(locked_assets.contains_key(env::consts::OS));
+    }
+
+    #[test]
+    fn lock_file_roundtrip() {
+        let dir = TempDir::new("tool-sync-test").unwrap();
+        let lock_path = lock_path(&dir.path().join(".tool.toml"));
+
+        let lock_file = ripgrep_lock_file();
+        lock_file.save(&lock_path).unwrap();
+
+        assert_eq!(LockFile::load(&lock_path).unwrap(), lock_file);
+    }
+
+    #[test]
+    fn locked_asset_matches_config() {
+        let lock_file = ripgrep_lock_file();
+
+        assert!(lock_file
+            .locked_asset("ripgrep", &ripgrep_info(ToolInfoTag::Latest))
+            .is_ok());
+        assert!(lock_file
+            .locked_asset(
+                "ripgrep",
+                &ripgrep_info(ToolInfoTag::Specific("13.0.0".to_string()))
+            )
+            .is_ok());
+        assert!(lock_file
+            .locked_asset(
+                "ripgrep",
+                &ripgrep_info(ToolInfoTag::Requirement(VersionReq::parse("^13").unwrap()))
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn locked_asset_is_out_of_date() {
+        let lock_file = ripgrep_lock_file();
+
+        let mut forked = ripgrep_info(ToolInfoTag::Latest);
+        forked.owner = "me".to_string();
+
+        assert!(lock_file.locked_asset("ripgrep", &forked).is_err());
+        assert!(lock_file
+            .locked_asset(
+                "ripgrep",
+                &ripgrep_info(ToolInfoTag::Specific("12.1.1".to_string()))
+            )
+            .is_err());
+        assert!(lock_file
+            .locked_asset(
+                "ripgrep",
+                &ripgrep_info(ToolInfoTag::Requirement(VersionReq::parse("^12").unwrap()))
+            )
+            .is_err());
+        assert!(lock_file
+            .locked_asset("bat", &ripgrep_info(ToolInfoTag::Latest))
+            .is_err());
+    }
+}
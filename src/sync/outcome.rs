@@ -0,0 +1,281 @@
+use serde::Serialize;
+use std::fmt::Display;
+
+/// Exit code of 'tool sync' when at least one tool failed to install
+pub const SYNC_FAILURE_EXIT_CODE: i32 = 2;
+
+/// Result of syncing a single tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallOutcome {
+    /// The tool was installed from the release with the given tag
+    Installed(String),
+
+    /// The tool from the release with the given tag is already installed
+    UpToDate(String),
+
+    /// The tool wasn't installed for the given reason, e.g. it has no asset
+    /// for this OS
+    Skipped(String),
+
+    /// The tool failed to install with the given error
+    Failed(InstallError),
+}
+
+/// Stage of syncing a tool that failed
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The tool is configured incorrectly or missing in the lock file
+    Config,
+
+    /// Fetching the release or downloading the asset failed
+    Network,
+
+    /// The release doesn't have an asset matching the config
+    AssetNotFound,
+
+    /// The downloaded asset doesn't match the expected hash
+    Checksum,
+
+    /// The asset can't be unpacked or doesn't contain the executable
+    Unpack,
+
+    /// Copying files to their directories failed
+    Io,
+}
+
+/// Error of a single tool with the stage where it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl InstallError {
+    pub fn new(kind: ErrorKind, message: String) -> InstallError {
+        InstallError { kind, message }
+    }
+}
+
+/// Wrap any error into 'InstallError' of the given kind, e.g.
+/// `.map_err(fail(ErrorKind::Network))`
+pub fn fail<E: Display>(kind: ErrorKind) -> impl Fn(E) -> InstallError {
+    move |e| InstallError::new(kind, e.to_string())
+}
+
+impl InstallOutcome {
+    /// Whether the tool is in 'store_directory' after syncing
+    pub fn is_installed(&self) -> bool {
+        matches!(
+            self,
+            InstallOutcome::Installed(_) | InstallOutcome::UpToDate(_)
+        )
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self, InstallOutcome::Failed(_))
+    }
+}
+
+/// Outcomes of all tools from a single run of 'tool sync'
+pub struct SyncSummary {
+    outcomes: Vec<(String, InstallOutcome)>,
+}
+
+/// Outcome of a single tool in the JSON report
+#[derive(Serialize)]
+struct ToolReport<'a> {
+    tool: &'a str,
+    outcome: &'static str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag_name: Option<&'a str>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_kind: Option<ErrorKind>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+/// The whole JSON report
+#[derive(Serialize)]
+struct SummaryReport<'a> {
+    installed: usize,
+    up_to_date: usize,
+    skipped: usize,
+    failed: usize,
+    tools: Vec<ToolReport<'a>>,
+}
+
+impl SyncSummary {
+    pub fn new(outcomes: Vec<(String, InstallOutcome)>) -> SyncSummary {
+        SyncSummary { outcomes }
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.outcomes
+            .iter()
+            .any(|(_, outcome)| outcome.is_failure())
+    }
+
+    fn count(&self, is_kind: fn(&InstallOutcome) -> bool) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|(_, outcome)| is_kind(outcome))
+            .count()
+    }
+
+    /// Counts of each outcome followed by the list of failed tools with their
+    /// errors
+    pub fn display(&self) -> String {
+        let mut summary = format!(
+            "Synced {} tools: {} installed, {} up to date, {} skipped, {} failed",
+            self.outcomes.len(),
+            self.count(|outcome| matches!(outcome, InstallOutcome::Installed(_))),
+            self.count(|outcome| matches!(outcome, InstallOutcome::UpToDate(_))),
+            self.count(|outcome| matches!(outcome, InstallOutcome::Skipped(_))),
+            self.count(InstallOutcome::is_failure),
+        );
+
+        if self.has_failures() {
+            summary.push_str("\n\nFailed tools:\n");
+
+            for (tool_name, outcome) in self.outcomes.iter() {
+                if let InstallOutcome::Failed(e) = outcome {
+                    summary.push_str(&format!("\n    * {}: {}", tool_name, e.message));
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// The same summary as a single JSON document
+    pub fn to_json(&self) -> String {
+        let tools = self
+            .outcomes
+            .iter()
+            .map(|(tool_name, outcome)| {
+                let mut report = ToolReport {
+                    tool: tool_name,
+                    outcome: "",
+                    tag_name: None,
+                    reason: None,
+                    error_kind: None,
+                    error: None,
+                };
+
+                match outcome {
+                    InstallOutcome::Installed(tag_name) => {
+                        report.outcome = "installed";
+                        report.tag_name = Some(tag_name);
+                    }
+                    InstallOutcome::UpToDate(tag_name) => {
+                        report.outcome = "up_to_date";
+                        report.tag_name = Some(tag_name);
+                    }
+                    InstallOutcome::Skipped(reason) => {
+                        report.outcome = "skipped";
+                        report.reason = Some(reason);
+                    }
+                    InstallOutcome::Failed(e) => {
+                        report.outcome = "failed";
+                        report.error_kind = Some(e.kind);
+                        report.error = Some(&e.message);
+                    }
+                }
+
+                report
+            })
+            .collect();
+
+        let report = SummaryReport {
+            installed: self.count(|outcome| matches!(outcome, InstallOutcome::Installed(_))),
+            up_to_date: self.count(|outcome| matches!(outcome, InstallOutcome::UpToDate(_))),
+            skipped: self.count(|outcome| matches!(outcome, InstallOutcome::Skipped(_))),
+            failed: self.count(InstallOutcome::is_failure),
+            tools,
+        };
+
+        // serializing a struct of strings and numbers never fails
+        serde_json::to_string(&report).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed(kind: ErrorKind, message: &str) -> InstallOutcome {
+        InstallOutcome::Failed(InstallError::new(kind, message.to_owned()))
+    }
+
+    #[test]
+    fn summary_without_failures() {
+        let summary = SyncSummary::new(vec![
+            (
+                "bat".to_owned(),
+                InstallOutcome::Installed("v0.22.1".to_owned()),
+            ),
+            (
+                "fd".to_owned(),
+                InstallOutcome::UpToDate("v8.4.0".to_owned()),
+            ),
+        ]);
+
+        assert!(!summary.has_failures());
+        assert_eq!(
+            summary.display(),
+            "Synced 2 tools: 1 installed, 1 up to date, 0 skipped, 0 failed"
+        );
+    }
+
+    #[test]
+    fn summary_lists_failed_tools() {
+        let summary = SyncSummary::new(vec![
+            (
+                "bat".to_owned(),
+                failed(ErrorKind::AssetNotFound, "No asset"),
+            ),
+            (
+                "fd".to_owned(),
+                InstallOutcome::Skipped("No asset".to_owned()),
+            ),
+            ("ripgrep".to_owned(), failed(ErrorKind::Network, "Timeout")),
+        ]);
+
+        assert!(summary.has_failures());
+        assert_eq!(
+            summary.display(),
+            r#"Synced 3 tools: 0 installed, 0 up to date, 1 skipped, 2 failed
+
+Failed tools:
+
+    * bat: No asset
+    * ripgrep: Timeout"#
+        );
+    }
+
+    #[test]
+    fn summary_as_json() {
+        let summary = SyncSummary::new(vec![
+            (
+                "bat".to_owned(),
+                InstallOutcome::Installed("v0.22.1".to_owned()),
+            ),
+            (
+                "ripgrep".to_owned(),
+                failed(ErrorKind::Checksum, "Mismatch"),
+            ),
+        ]);
+
+        assert_eq!(
+            summary.to_json(),
+            r#"{"installed":1,"up_to_date":0,"skipped":0,"failed":1,"tools":[{"tool":"bat","outcome":"installed","tag_name":"v0.22.1"},{"tool":"ripgrep","outcome":"failed","error_kind":"checksum","error":"Mismatch"}]}"#
+        );
+    }
+}
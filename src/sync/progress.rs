@@ -1,19 +1,32 @@
 use console::{style, Emoji};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::collections::HashMap;
+use std::sync::Mutex;
 
+use super::report::{Event, Reporter};
+
+/// Renders progress of all tools as progress bars, one line per tool
 pub struct SyncProgress {
     max_tool_size: usize,
     max_tag_size: usize,
     multi_progress: MultiProgress,
+
+    /// Lines of all tools, created upfront to keep the order of the config
+    message_bars: HashMap<String, ProgressBar>,
+
+    /// Bars of assets that are being downloaded, keyed by tool name
+    download_bars: Mutex<HashMap<String, ProgressBar>>,
 }
 
 const SUCCESS: Emoji<'_, '_> = Emoji("✅  ", "OK ");
 const FAILURE: Emoji<'_, '_> = Emoji("⛔  ", "NO ");
+const UP_TO_DATE: Emoji<'_, '_> = Emoji("💤  ", "== ");
+const SKIPPED: Emoji<'_, '_> = Emoji("⏩  ", "-- ");
 const PROCESS: Emoji<'_, '_> = Emoji("📥  ", ".. ");
 const MIN_TAG_SIZE: usize = 8;
 
 impl SyncProgress {
-    /// Creates new `SyncProgress` from a list of tools.
+    /// Creates new `SyncProgress` from a list of tools and their tags.
     /// !!! The given `Vec` must be non-empty !!!
     pub fn new(tools: Vec<String>, tags: Vec<String>) -> SyncProgress {
         // unwrap is safe here because 'new' is called with a non-empty vector
@@ -26,13 +39,20 @@ impl SyncProgress {
             .max()
             .unwrap_or(MIN_TAG_SIZE);
 
-        let multi_progress = MultiProgress::new();
-
-        SyncProgress {
+        let mut sync_progress = SyncProgress {
             max_tool_size,
             max_tag_size,
-            multi_progress,
+            multi_progress: MultiProgress::new(),
+            message_bars: HashMap::new(),
+            download_bars: Mutex::new(HashMap::new()),
+        };
+
+        for (tool_name, tag) in tools.iter().zip(tags.iter()) {
+            let pb_msg = sync_progress.create_message_bar(tool_name, tag);
+            sync_progress.message_bars.insert(tool_name.clone(), pb_msg);
         }
+
+        sync_progress
     }
 
     fn fmt_prefix(&self, emoji: Emoji, tool_name: &str, tag_name: &str) -> String {
@@ -47,7 +67,7 @@ impl SyncProgress {
         format!("{}{}", emoji, aligned_tool)
     }
 
-    pub fn create_message_bar(&self, tool_name: &str, tag_name: &str) -> ProgressBar {
+    fn create_message_bar(&self, tool_name: &str, tag_name: &str) -> ProgressBar {
         let message_style = ProgressStyle::with_template("{prefix:.bold.dim} {msg}").unwrap();
 
         self.multi_progress.add(
@@ -57,32 +77,98 @@ impl SyncProgress {
         )
     }
 
-    pub fn create_progress_bar(&self, size: u64) -> ProgressBar {
-        let bar_style =
-            ProgressStyle::with_template("{bytes}/{total_bytes} {wide_bar:.cyan/blue}").unwrap();
+    /// Create a download progress bar right under the line of its tool. Only
+    /// downloaded bytes are shown when the size of the asset is unknown.
+    fn create_progress_bar(&self, pb_msg: &ProgressBar, size: Option<u64>) -> ProgressBar {
+        let pb_downloading = match size {
+            Some(size) => ProgressBar::new(size).with_style(
+                ProgressStyle::with_template("{bytes}/{total_bytes} {wide_bar:.cyan/blue}")
+                    .unwrap(),
+            ),
+            None => ProgressBar::new_spinner()
+                .with_style(ProgressStyle::with_template("{bytes} {spinner:.cyan}").unwrap()),
+        };
+
+        self.multi_progress.insert_after(pb_msg, pb_downloading)
+    }
 
-        self.multi_progress
-            .add(ProgressBar::new(size).with_style(bar_style))
+    fn download_bars(&self) -> std::sync::MutexGuard<'_, HashMap<String, ProgressBar>> {
+        // the lock is poisoned only when another installation panicked
+        self.download_bars.lock().unwrap()
     }
 
-    pub fn finish_progress(pb: ProgressBar) {
-        pb.finish_and_clear()
+    fn finish_download(&self, tool_name: &str) {
+        if let Some(pb) = self.download_bars().remove(tool_name) {
+            pb.finish_and_clear();
+        }
     }
 
-    pub fn success(&self, pb: ProgressBar, tool_name: &str, tag_name: &str) {
-        pb.set_prefix(self.fmt_prefix(SUCCESS, tool_name, tag_name));
+    fn finish(&self, pb: &ProgressBar, emoji: Emoji, tool_name: &str, tag_name: &str, msg: String) {
+        self.finish_download(tool_name);
 
-        let success_msg = format!("{}", style("Completed!").bold().green());
-        pb.set_message(success_msg);
+        pb.set_prefix(self.fmt_prefix(emoji, tool_name, tag_name));
+        pb.set_message(msg);
         pb.finish();
     }
+}
 
-    pub fn failure(&self, pb: ProgressBar, tool_name: &str, tag_name: &str, err_msg: String) {
-        pb.set_prefix(self.fmt_prefix(FAILURE, tool_name, tag_name));
-
-        let failure_msg = format!("{}", style(err_msg).red());
-        pb.set_message(failure_msg);
-        pb.finish();
+impl Reporter for SyncProgress {
+    fn report(&self, tool_name: &str, event: Event) {
+        let pb = match self.message_bars.get(tool_name) {
+            Some(pb) => pb,
+            None => return,
+        };
+
+        match event {
+            Event::Started { .. } | Event::ReleaseResolved { .. } => {}
+            Event::FetchingRelease => pb.set_message("Fetching info..."),
+            Event::DownloadStarted { size, .. } => {
+                pb.set_message("Downloading...");
+                let pb_downloading = self.create_progress_bar(pb, size);
+                self.download_bars()
+                    .insert(tool_name.to_owned(), pb_downloading);
+            }
+            Event::DownloadProgress { downloaded, .. } => {
+                if let Some(pb_downloading) = self.download_bars().get(tool_name) {
+                    pb_downloading.set_position(downloaded);
+                }
+            }
+            Event::DownloadFinished { .. } => {
+                self.finish_download(tool_name);
+                pb.set_message("Downloaded!");
+            }
+            Event::ChecksumSkipped { checksum_asset } => {
+                // the message would be replaced by the outcome, so it's
+                // printed above the progress bars
+                pb.println(format!(
+                    "{}: {} {} doesn't list the asset",
+                    tool_name,
+                    style("checksum not verified:").yellow(),
+                    checksum_asset
+                ));
+            }
+            Event::Installed { tag_name, .. } | Event::Locked { tag_name, .. } => {
+                let success_msg = format!("{}", style("Completed!").bold().green());
+                self.finish(pb, SUCCESS, tool_name, tag_name, success_msg);
+            }
+            Event::UpToDate { tag_name } => {
+                let up_to_date_msg = format!("{}", style("Up to date").bold().dim());
+                self.finish(pb, UP_TO_DATE, tool_name, tag_name, up_to_date_msg);
+            }
+            Event::Skipped { tag, reason } => {
+                let skipped_msg = format!("{}", style(reason).yellow());
+                self.finish(pb, SKIPPED, tool_name, tag, skipped_msg);
+            }
+            Event::Failed { tag, message, .. } => {
+                let failure_msg = format!("{}", style(message).red());
+                self.finish(pb, FAILURE, tool_name, tag, failure_msg);
+            }
+            Event::Checked { .. } => {
+                // the status table is printed after all tools are checked
+                self.finish_download(tool_name);
+                pb.finish_and_clear();
+            }
+        }
     }
 }
 
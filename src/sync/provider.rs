@@ -0,0 +1,737 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+
+use crate::config::schema::Config;
+use crate::model::release::{Asset, Release};
+use crate::model::tool::{ReleaseSource, ToolInfo, ToolInfoTag};
+use crate::model::url_template::{expand_url, url_file_name};
+
+/// Base URL of the public GitHub API
+const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Host of the public GitLab instance
+const DEFAULT_GITLAB_HOST: &str = "gitlab.com";
+
+/// Host of the most popular public Gitea instance
+const DEFAULT_GITEA_HOST: &str = "codeberg.org";
+
+/// Environment variable overriding 'github_api_url' from the config
+const GITHUB_API_URL_ENV: &str = "TOOL_SYNC_GITHUB_API_URL";
+
+/// Environment variable with the token for the public GitHub API
+const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+
+/// Environment variable with the token for GitLab
+const GITLAB_TOKEN_ENV: &str = "GITLAB_TOKEN";
+
+/// Number of releases requested at once when listing all releases. Gitea
+/// instances return at most 50 by default.
+const RELEASES_PER_PAGE: usize = 50;
+
+/// Error for tools with 'url' but without 'tag'. Such tools aren't
+/// configured, so this shouldn't happen.
+const URL_WITHOUT_TAG: &str = "Downloading from 'url' requires 'tag'";
+
+const USER_AGENT: &str = "chshersh/tool-sync-0.1.0";
+
+/// API of the service where releases of a tool are published, together with
+/// the token to access it
+#[derive(Debug, PartialEq, Eq)]
+pub enum Provider {
+    GitHub {
+        api_url: String,
+        token: Option<String>,
+    },
+    GitLab {
+        api_url: String,
+        token: Option<String>,
+    },
+    Gitea {
+        api_url: String,
+        token: Option<String>,
+    },
+
+    /// No API at all: the asset is downloaded from the URL template
+    Url { template: String, exe_name: String },
+}
+
+impl Provider {
+    /// URL of the release with the given tag in the API. Version
+    /// requirements have no such URL, as well as direct URLs without a tag.
+    pub fn release_url(&self, owner: &str, repo: &str, tag: &ToolInfoTag) -> Option<String> {
+        match self {
+            Provider::GitHub { api_url, .. } | Provider::Gitea { api_url, .. } => {
+                tag.to_str_version().map(|version| {
+                    format!(
+                        "{api_url}/repos/{owner}/{repo}/releases/{version}",
+                        api_url = api_url,
+                        owner = owner,
+                        repo = repo,
+                        version = version,
+                    )
+                })
+            }
+            Provider::GitLab { api_url, .. } => {
+                let project = encode_path_segment(&format!("{}/{}", owner, repo));
+
+                match tag {
+                    ToolInfoTag::Latest => Some(format!(
+                        "{}/projects/{}/releases/permalink/latest",
+                        api_url, project
+                    )),
+                    ToolInfoTag::Specific(tag) => Some(format!(
+                        "{}/projects/{}/releases/{}",
+                        api_url,
+                        project,
+                        encode_path_segment(tag)
+                    )),
+                    ToolInfoTag::Requirement(_) => None,
+                }
+            }
+            // the asset itself is the release
+            Provider::Url { template, exe_name } => match tag {
+                ToolInfoTag::Specific(tag) => {
+                    Some(expand_url(template, env::consts::OS, tag, exe_name))
+                }
+                ToolInfoTag::Latest | ToolInfoTag::Requirement(_) => None,
+            },
+        }
+    }
+
+    /// Fetch info about the release without downloading any assets
+    pub fn fetch_release(
+        &self,
+        owner: &str,
+        repo: &str,
+        tag: &ToolInfoTag,
+    ) -> Result<Release, Box<dyn Error>> {
+        let release_url = self.release_url(owner, repo, tag).ok_or(match self {
+            Provider::Url { .. } => URL_WITHOUT_TAG,
+            _ => "Version requirements are resolved from the list of all releases",
+        })?;
+        let api_request = || self.authorize(ureq::get(&release_url).set("User-Agent", USER_AGENT));
+
+        match self {
+            Provider::GitHub { .. } => {
+                let release: Release = api_request()
+                    .set("Accept", "application/vnd.github+json")
+                    .call()?
+                    .into_json()?;
+
+                Ok(release)
+            }
+            Provider::GitLab { .. } => {
+                let release: GitLabRelease = api_request().call()?.into_json()?;
+
+                Ok(release.into_release())
+            }
+            // Gitea releases and assets have the same fields as GitHub ones
+            Provider::Gitea { .. } => {
+                let release: Release = api_request().call()?.into_json()?;
+
+                Ok(release)
+            }
+            // A single asset at the expanded URL, nothing to fetch
+            Provider::Url { .. } => match tag {
+                ToolInfoTag::Specific(tag) => Ok(Release {
+                    tag_name: tag.clone(),
+                    assets: self.url_asset(tag, env::consts::OS).into_iter().collect(),
+                    prerelease: false,
+                    draft: false,
+                    published_at: None,
+                }),
+                ToolInfoTag::Latest | ToolInfoTag::Requirement(_) => Err(URL_WITHOUT_TAG.into()),
+            },
+        }
+    }
+
+    /// The asset at the URL expanded for the given OS. Releases of other
+    /// providers list their assets instead.
+    pub fn url_asset(&self, tag_name: &str, os: &str) -> Option<Asset> {
+        match self {
+            Provider::Url { template, exe_name } => {
+                let url = expand_url(template, os, tag_name, exe_name);
+
+                Some(Asset {
+                    id: 0,
+                    name: url_file_name(&url).to_owned(),
+                    size: None,
+                    browser_download_url: url,
+                })
+            }
+            Provider::GitHub { .. } | Provider::GitLab { .. } | Provider::Gitea { .. } => None,
+        }
+    }
+
+    /// URL of the list of all releases in the API, without the page. Direct
+    /// URLs have no releases to list.
+    fn releases_url(&self, owner: &str, repo: &str) -> Option<String> {
+        match self {
+            Provider::GitHub { api_url, .. } => Some(format!(
+                "{}/repos/{}/{}/releases?per_page={}",
+                api_url, owner, repo, RELEASES_PER_PAGE
+            )),
+            Provider::GitLab { api_url, .. } => Some(format!(
+                "{}/projects/{}/releases?per_page={}",
+                api_url,
+                encode_path_segment(&format!("{}/{}", owner, repo)),
+                RELEASES_PER_PAGE
+            )),
+            Provider::Gitea { api_url, .. } => Some(format!(
+                "{}/repos/{}/{}/releases?limit={}",
+                api_url, owner, repo, RELEASES_PER_PAGE
+            )),
+            Provider::Url { .. } => None,
+        }
+    }
+
+    /// Fetch info about all releases, newest first, page by page until a
+    /// page isn't full
+    pub fn fetch_releases(&self, owner: &str, repo: &str) -> Result<Vec<Release>, Box<dyn Error>> {
+        let releases_url = self
+            .releases_url(owner, repo)
+            .ok_or("Releases can't be listed for tools with 'url'")?;
+
+        let mut releases = Vec::new();
+
+        for page in 1.. {
+            let page_url = format!("{}&page={}", releases_url, page);
+            let req = self.authorize(ureq::get(&page_url).set("User-Agent", USER_AGENT));
+
+            let page_releases: Vec<Release> = match self {
+                Provider::GitHub { .. } => req
+                    .set("Accept", "application/vnd.github+json")
+                    .call()?
+                    .into_json()?,
+                Provider::GitLab { .. } => req
+                    .call()?
+                    .into_json::<Vec<GitLabRelease>>()?
+                    .into_iter()
+                    .map(GitLabRelease::into_release)
+                    .collect(),
+                Provider::Gitea { .. } | Provider::Url { .. } => req.call()?.into_json()?,
+            };
+
+            let is_last_page = page_releases.len() < RELEASES_PER_PAGE;
+            releases.extend(page_releases);
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        Ok(releases)
+    }
+
+    /// Request to download the asset of a release
+    pub fn asset_request(&self, owner: &str, repo: &str, asset: &Asset) -> ureq::Request {
+        match self {
+            Provider::GitHub { api_url, .. } => {
+                let asset_url = format!(
+                    "{api_url}/repos/{owner}/{repo}/releases/assets/{asset_id}",
+                    api_url = api_url,
+                    owner = owner,
+                    repo = repo,
+                    asset_id = asset.id
+                );
+
+                self.authorize(
+                    ureq::get(&asset_url)
+                        .set("Accept", "application/octet-stream")
+                        .set("User-Agent", USER_AGENT),
+                )
+            }
+            Provider::GitLab { api_url, .. } | Provider::Gitea { api_url, .. } => {
+                let req = ureq::get(&asset.browser_download_url).set("User-Agent", USER_AGENT);
+
+                // release links can point anywhere, and other hosts must not
+                // get the token
+                if url_host(&asset.browser_download_url) == url_host(api_url) {
+                    self.authorize(req)
+                } else {
+                    req
+                }
+            }
+            Provider::Url { .. } => {
+                ureq::get(&asset.browser_download_url).set("User-Agent", USER_AGENT)
+            }
+        }
+    }
+
+    fn authorize(&self, req: ureq::Request) -> ureq::Request {
+        match self {
+            Provider::GitHub {
+                token: Some(token), ..
+            }
+            | Provider::Gitea {
+                token: Some(token), ..
+            } => req.set("Authorization", &format!("token {}", token)),
+            Provider::GitLab {
+                token: Some(token), ..
+            } => req.set("PRIVATE-TOKEN", token),
+            _ => req,
+        }
+    }
+}
+
+/// API URLs and tokens of all providers from the config
+pub struct ApiConfig {
+    github_api_url: String,
+
+    /// Names of environment variables with GitHub tokens, keyed by host
+    github_tokens: BTreeMap<String, String>,
+}
+
+impl ApiConfig {
+    /// The GitHub API URL is taken from the environment variable, the config
+    /// or the public GitHub API, in this order
+    pub fn from_config(config: &Config) -> ApiConfig {
+        let github_api_url = env::var(GITHUB_API_URL_ENV)
+            .ok()
+            .filter(|url| !url.is_empty())
+            .or_else(|| config.github_api_url.clone())
+            .unwrap_or_else(|| DEFAULT_GITHUB_API_URL.to_owned());
+
+        ApiConfig {
+            github_api_url,
+            github_tokens: config.github_tokens.clone(),
+        }
+    }
+
+    /// Provider of the tool with its API URL and token
+    pub fn provider(&self, tool_info: &ToolInfo) -> Provider {
+        if let Some(template) = &tool_info.url {
+            let exe_name = tool_info
+                .exe_name
+                .executables()
+                .first()
+                .map_or("", |(name, _)| *name);
+
+            return Provider::Url {
+                template: template.clone(),
+                exe_name: exe_name.to_owned(),
+            };
+        }
+
+        let api_url = self.api_url(tool_info);
+        let token = self
+            .token_env(tool_info, &api_url)
+            .and_then(|env_var| env::var(env_var).ok());
+
+        match tool_info.source {
+            ReleaseSource::GitHub => Provider::GitHub { api_url, token },
+            ReleaseSource::GitLab => Provider::GitLab { api_url, token },
+            ReleaseSource::Gitea => Provider::Gitea { api_url, token },
+        }
+    }
+
+    /// Name of the environment variable with the token for the tool. Gitea
+    /// instances have no default token.
+    fn token_env<'a>(&'a self, tool_info: &'a ToolInfo, api_url: &str) -> Option<&'a str> {
+        match (&tool_info.token_env, tool_info.source) {
+            (Some(token_env), _) => Some(token_env),
+            (None, ReleaseSource::GitHub) => self.github_token_env(api_url),
+            (None, ReleaseSource::GitLab) => Some(GITLAB_TOKEN_ENV),
+            (None, ReleaseSource::Gitea) => None,
+        }
+    }
+
+    /// Base URL of the API for the tool, without the trailing slash
+    fn api_url(&self, tool_info: &ToolInfo) -> String {
+        let api_url = match (&tool_info.api_url, &tool_info.host, tool_info.source) {
+            (Some(api_url), _, _) => api_url.clone(),
+            (None, None, ReleaseSource::GitHub) => self.github_api_url.clone(),
+            (None, Some(host), ReleaseSource::GitHub) if url_host(host) == "github.com" => {
+                DEFAULT_GITHUB_API_URL.to_owned()
+            }
+            (None, Some(host), ReleaseSource::GitHub) => {
+                format!("{}/api/v3", instance_url(host))
+            }
+            (None, host, ReleaseSource::GitLab) => format!(
+                "{}/api/v4",
+                instance_url(host.as_deref().unwrap_or(DEFAULT_GITLAB_HOST))
+            ),
+            (None, host, ReleaseSource::Gitea) => format!(
+                "{}/api/v1",
+                instance_url(host.as_deref().unwrap_or(DEFAULT_GITEA_HOST))
+            ),
+        };
+
+        api_url.trim_end_matches('/').to_owned()
+    }
+
+    /// Name of the environment variable with the GitHub token for the API.
+    /// Tokens are never sent to other hosts, so 'GITHUB_TOKEN' is used only
+    /// for the public GitHub API unless configured otherwise.
+    fn github_token_env(&self, api_url: &str) -> Option<&str> {
+        let host = url_host(api_url);
+
+        match self.github_tokens.get(host) {
+            Some(env_var) => Some(env_var),
+            None if host == url_host(DEFAULT_GITHUB_API_URL) => Some(GITHUB_TOKEN_ENV),
+            None => None,
+        }
+    }
+}
+
+/// URL of an instance from its host, e.g. 'https://gitlab.example.com'.
+/// Hosts with an explicit scheme are used as is.
+fn instance_url(host: &str) -> String {
+    let host = host.trim_end_matches('/');
+
+    if host.contains("://") {
+        host.to_owned()
+    } else {
+        format!("https://{}", host)
+    }
+}
+
+/// Host of the URL with the port, e.g. 'github.example.com:8443'
+fn url_host(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme.split('/').next().unwrap_or_default()
+}
+
+/// Percent-encode all characters except unreserved ones, e.g. to pass
+/// 'group/project' as a single segment of the path
+fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Release from the GitLab API. Its assets are links to arbitrary URLs.
+#[derive(Deserialize, Debug)]
+struct GitLabRelease {
+    tag_name: String,
+    assets: GitLabAssets,
+
+    /// Releases with 'released_at' in the future
+    #[serde(default)]
+    upcoming_release: bool,
+
+    #[serde(default)]
+    released_at: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitLabAssets {
+    links: Vec<GitLabLink>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitLabLink {
+    id: u32,
+    name: String,
+    url: String,
+    direct_asset_url: Option<String>,
+}
+
+impl GitLabRelease {
+    fn into_release(self) -> Release {
+        Release {
+            tag_name: self.tag_name,
+            assets: self
+                .assets
+                .links
+                .into_iter()
+                .map(|link| Asset {
+                    id: link.id,
+                    name: link.name,
+                    size: None,
+                    browser_download_url: link.direct_asset_url.unwrap_or(link.url),
+                })
+                .collect(),
+            prerelease: self.upcoming_release,
+            draft: false,
+            published_at: self.released_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::asset_name::AssetName;
+    use crate::model::exe_name::ExeName;
+    use semver::VersionReq;
+
+    fn tool_info(source: ReleaseSource, host: Option<&str>, api_url: Option<&str>) -> ToolInfo {
+        ToolInfo {
+            owner: "OWNER".to_owned(),
+            repo: "REPO".to_owned(),
+            exe_name: ExeName::Single("tool".to_owned()),
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+            },
+            api_url: api_url.map(String::from),
+            source,
+            host: host.map(String::from),
+            ..ToolInfo::default()
+        }
+    }
+
+    fn api_config(tokens: &[(&str, &str)]) -> ApiConfig {
+        ApiConfig {
+            github_api_url: DEFAULT_GITHUB_API_URL.to_owned(),
+            github_tokens: tokens
+                .iter()
+                .map(|(host, env_var)| (host.to_string(), env_var.to_string()))
+                .collect(),
+        }
+    }
+
+    fn github() -> Provider {
+        Provider::GitHub {
+            api_url: DEFAULT_GITHUB_API_URL.to_owned(),
+            token: None,
+        }
+    }
+
+    fn gitlab() -> Provider {
+        Provider::GitLab {
+            api_url: "https://gitlab.com/api/v4".to_owned(),
+            token: None,
+        }
+    }
+
+    #[test]
+    fn release_url_with_latest_tag_is_correct() {
+        assert_eq!(
+            github()
+                .release_url("OWNER", "REPO", &ToolInfoTag::Latest)
+                .unwrap(),
+            "https://api.github.com/repos/OWNER/REPO/releases/latest"
+        );
+    }
+
+    #[test]
+    fn release_url_with_specific_tag_is_correct() {
+        assert_eq!(
+            github()
+                .release_url(
+                    "OWNER",
+                    "REPO",
+                    &ToolInfoTag::Specific("SPECIFIC_TAG".to_string())
+                )
+                .unwrap(),
+            "https://api.github.com/repos/OWNER/REPO/releases/tags/SPECIFIC_TAG"
+        );
+    }
+
+    #[test]
+    fn gitlab_release_url() {
+        assert_eq!(
+            gitlab()
+                .release_url("group/subgroup", "REPO", &ToolInfoTag::Latest)
+                .unwrap(),
+            "https://gitlab.com/api/v4/projects/group%2Fsubgroup%2FREPO/releases/permalink/latest"
+        );
+        assert_eq!(
+            gitlab()
+                .release_url("OWNER", "REPO", &ToolInfoTag::Specific("v1.0+1".into()))
+                .unwrap(),
+            "https://gitlab.com/api/v4/projects/OWNER%2FREPO/releases/v1.0%2B1"
+        );
+    }
+
+    #[test]
+    fn no_release_url_for_requirement() {
+        let requirement = ToolInfoTag::Requirement(VersionReq::parse("^13").unwrap());
+
+        assert_eq!(github().release_url("OWNER", "REPO", &requirement), None);
+        assert_eq!(gitlab().release_url("OWNER", "REPO", &requirement), None);
+    }
+
+    #[test]
+    fn api_url_of_tool() {
+        let api = api_config(&[]);
+
+        let url = |source, host, api_url| api.api_url(&tool_info(source, host, api_url));
+
+        assert_eq!(
+            url(ReleaseSource::GitHub, None, None),
+            "https://api.github.com"
+        );
+        assert_eq!(
+            url(
+                ReleaseSource::GitHub,
+                None,
+                Some("https://github.example.com/api/v3/")
+            ),
+            "https://github.example.com/api/v3"
+        );
+        assert_eq!(
+            url(ReleaseSource::GitHub, Some("github.example.com"), None),
+            "https://github.example.com/api/v3"
+        );
+        assert_eq!(
+            url(ReleaseSource::GitHub, Some("github.com"), None),
+            "https://api.github.com"
+        );
+        assert_eq!(
+            url(ReleaseSource::GitLab, None, None),
+            "https://gitlab.com/api/v4"
+        );
+        assert_eq!(
+            url(ReleaseSource::GitLab, Some("http://localhost:8080/"), None),
+            "http://localhost:8080/api/v4"
+        );
+        assert_eq!(
+            url(ReleaseSource::Gitea, None, None),
+            "https://codeberg.org/api/v1"
+        );
+        assert_eq!(
+            url(ReleaseSource::Gitea, Some("gitea.example.com"), None),
+            "https://gitea.example.com/api/v1"
+        );
+    }
+
+    #[test]
+    fn github_token_per_host() {
+        let api = api_config(&[("github.example.com", "EXAMPLE_TOKEN")]);
+
+        assert_eq!(
+            api.github_token_env("https://api.github.com"),
+            Some("GITHUB_TOKEN")
+        );
+        assert_eq!(
+            api.github_token_env("https://github.example.com/api/v3"),
+            Some("EXAMPLE_TOKEN")
+        );
+        assert_eq!(
+            api.github_token_env("https://github.other.com/api/v3"),
+            None
+        );
+    }
+
+    #[test]
+    fn token_env_of_tool() {
+        let api = api_config(&[]);
+
+        let mut gitea_tool = tool_info(ReleaseSource::Gitea, None, None);
+        assert_eq!(
+            api.token_env(&gitea_tool, "https://codeberg.org/api/v1"),
+            None
+        );
+
+        gitea_tool.token_env = Some("CODEBERG_TOKEN".to_owned());
+        assert_eq!(
+            api.token_env(&gitea_tool, "https://codeberg.org/api/v1"),
+            Some("CODEBERG_TOKEN")
+        );
+
+        let gitlab_tool = tool_info(ReleaseSource::GitLab, None, None);
+        assert_eq!(
+            api.token_env(&gitlab_tool, "https://gitlab.com/api/v4"),
+            Some("GITLAB_TOKEN")
+        );
+    }
+
+    #[test]
+    fn gitea_release_url() {
+        let gitea = Provider::Gitea {
+            api_url: "https://codeberg.org/api/v1".to_owned(),
+            token: None,
+        };
+
+        assert_eq!(
+            gitea
+                .release_url("OWNER", "REPO", &ToolInfoTag::Latest)
+                .unwrap(),
+            "https://codeberg.org/api/v1/repos/OWNER/REPO/releases/latest"
+        );
+        assert_eq!(
+            gitea
+                .release_url("OWNER", "REPO", &ToolInfoTag::Specific("v1.0.0".into()))
+                .unwrap(),
+            "https://codeberg.org/api/v1/repos/OWNER/REPO/releases/tags/v1.0.0"
+        );
+    }
+
+    #[test]
+    fn gitlab_release_as_release() {
+        let release: GitLabRelease = serde_json::from_str(
+            r#"{
+                "tag_name": "v1.0.0",
+                "assets": {
+                    "count": 1,
+                    "links": [
+                        {
+                            "id": 1,
+                            "name": "tool-linux.tar.gz",
+                            "url": "https://gitlab.com/OWNER/REPO/-/jobs/1/artifacts/tool-linux.tar.gz",
+                            "direct_asset_url": "https://gitlab.com/OWNER/REPO/-/releases/v1.0.0/downloads/tool-linux.tar.gz",
+                            "link_type": "package"
+                        }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let release = release.into_release();
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert_eq!(release.assets.len(), 1);
+        assert_eq!(release.assets[0].name, "tool-linux.tar.gz");
+        assert_eq!(release.assets[0].size, None);
+        assert_eq!(
+            release.assets[0].browser_download_url,
+            "https://gitlab.com/OWNER/REPO/-/releases/v1.0.0/downloads/tool-linux.tar.gz"
+        );
+    }
+
+    #[test]
+    fn url_provider() {
+        let mut tool = tool_info(ReleaseSource::GitHub, None, None);
+        tool.url = Some("https://example.com/{version}/{exe}_{os}.zip".to_owned());
+
+        let provider = api_config(&[]).provider(&tool);
+        assert_eq!(
+            provider,
+            Provider::Url {
+                template: "https://example.com/{version}/{exe}_{os}.zip".to_owned(),
+                exe_name: "tool".to_owned(),
+            }
+        );
+
+        let release = provider
+            .fetch_release("OWNER", "REPO", &ToolInfoTag::Specific("v1.0.0".into()))
+            .unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+        assert_eq!(release.assets.len(), 1);
+        assert_eq!(
+            release.assets[0].browser_download_url,
+            expand_url(
+                "https://example.com/{version}/{exe}_{os}.zip",
+                env::consts::OS,
+                "v1.0.0",
+                "tool"
+            )
+        );
+
+        assert!(provider
+            .fetch_release("OWNER", "REPO", &ToolInfoTag::Latest)
+            .is_err());
+
+        // assets for other systems are expanded from the same template
+        let asset = provider.url_asset("v1.0.0", "macos").unwrap();
+        assert_eq!(asset.name, "tool_darwin.zip");
+        assert_eq!(
+            asset.browser_download_url,
+            "https://example.com/1.0.0/tool_darwin.zip"
+        );
+        assert!(github().url_asset("v1.0.0", "macos").is_none());
+    }
+}
@@ -0,0 +1,335 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::schema::Config;
+use crate::err;
+use crate::model::asset_name::mk_exe_name;
+use crate::model::tool::Tool;
+
+use super::checksum::sha256_file;
+use super::configure::configure_tool;
+use super::state::{load_state, save_state, InstallRecord, InstallState};
+use super::versions::tool_versions_directory;
+
+/// Remove all executables of a single tool from 'store_directory' together
+/// with its completions and man pages. Tools installed by 'tool-sync' are
+/// removed according to the install state, other tools by their configured
+/// executable names.
+pub fn uninstall(config: Config, tool_name: &str, dry_run: bool) {
+    let store_directory = config.ensure_store_directory();
+    let mut state = load_state(&store_directory);
+
+    let paths = match state.tools.get(tool_name) {
+        Some(record) => {
+            // the state record is dropped even if the files were removed manually
+            let is_removed = !record
+                .exe_names()
+                .iter()
+                .any(|exe_name| store_directory.join(exe_name).is_file());
+
+            if is_removed {
+                report_forgotten(tool_name, dry_run);
+            }
+
+            removable_files(&store_directory, record)
+        }
+        None => configured_files(&config, &store_directory, tool_name),
+    };
+
+    for path in paths.iter() {
+        report_removal(path, dry_run);
+    }
+
+    remove_versions(&store_directory, tool_name, dry_run);
+
+    if !dry_run {
+        for path in paths.iter() {
+            if let Err(e) = fs::remove_file(path) {
+                err::abort_with(&format!("Error removing {}: {}", path.display(), e));
+            }
+        }
+
+        state.tools.remove(tool_name);
+        save_state(&state, &store_directory);
+    }
+}
+
+/// Remove executables of tools that were installed by 'tool-sync' but are no
+/// longer present in the config
+pub fn prune(config: Config, dry_run: bool) {
+    let store_directory = config.ensure_store_directory();
+    let mut state = load_state(&store_directory);
+
+    let stale_tools = stale_tools(&config, &state);
+
+    if stale_tools.is_empty() {
+        println!("Nothing to prune");
+        return;
+    }
+
+    for tool_name in stale_tools {
+        // unwrap is safe here because stale tools are taken from the state
+        let record = state.tools.get(&tool_name).unwrap();
+
+        for path in removable_files(&store_directory, record) {
+            report_removal(&path, dry_run);
+
+            if !dry_run {
+                if let Err(e) = remove_if_exists(&path) {
+                    err::abort_with(&format!("Error removing {}: {}", path.display(), e));
+                }
+            }
+        }
+
+        remove_versions(&store_directory, &tool_name, dry_run);
+
+        if !dry_run {
+            state.tools.remove(&tool_name);
+        }
+    }
+
+    if !dry_run {
+        save_state(&state, &store_directory);
+    }
+}
+
+/// Existing files of the tool installed by 'tool-sync'. Executables that
+/// don't match the hashes recorded at installation were replaced or changed
+/// by someone else, so they are skipped with a warning.
+fn removable_files(store_directory: &Path, record: &InstallRecord) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    for executable in record.executables.iter() {
+        let path = store_directory.join(&executable.name);
+
+        if !path.is_file() {
+            continue;
+        }
+
+        match sha256_file(&path) {
+            Ok(sha256) if sha256 == executable.sha256 => paths.push(path),
+            _ => eprintln!(
+                "Skipping {}: it was changed after the installation by 'tool-sync'",
+                path.display()
+            ),
+        }
+    }
+
+    paths.extend(
+        record
+            .extra_files
+            .iter()
+            .filter(|path| path.is_file())
+            .cloned(),
+    );
+
+    paths
+}
+
+/// Existing executables of a tool that isn't in the install state, found by
+/// the configured names. Exits with an error if there are none.
+fn configured_files(config: &Config, store_directory: &Path, tool_name: &str) -> Vec<PathBuf> {
+    let config_asset = config.tools.get(tool_name).cloned().unwrap_or_default();
+
+    let exe_names: Vec<String> = match configure_tool(tool_name, &config_asset) {
+        Tool::Known(tool_info) => tool_info
+            .exe_name
+            .installed_names()
+            .into_iter()
+            .map(mk_exe_name)
+            .collect(),
+        Tool::Error(e) => {
+            err::abort_with(&format!(
+                "Don't know the executable name of '{}': {}",
+                tool_name,
+                e.display()
+            ));
+        }
+    };
+
+    let paths: Vec<PathBuf> = exe_names
+        .iter()
+        .map(|exe_name| store_directory.join(exe_name))
+        .filter(|exe_path| exe_path.is_file())
+        .collect();
+
+    if paths.is_empty() {
+        err::abort_with(&format!(
+            "Tool '{}' is not installed: {} doesn't exist in {}",
+            tool_name,
+            exe_names.join(", "),
+            store_directory.display()
+        ));
+    }
+
+    paths
+}
+
+/// Tools installed by 'tool-sync' that are not in the config anymore. A tool
+/// is not stale if another configured tool installed any of its executables.
+fn stale_tools(config: &Config, state: &InstallState) -> Vec<String> {
+    let configured_exes: Vec<&str> = state
+        .tools
+        .iter()
+        .filter(|(tool_name, _)| config.tools.contains_key(*tool_name))
+        .flat_map(|(_, record)| record.exe_names())
+        .collect();
+
+    state
+        .tools
+        .iter()
+        .filter(|(tool_name, record)| {
+            !config.tools.contains_key(*tool_name)
+                && !record
+                    .exe_names()
+                    .iter()
+                    .any(|exe_name| configured_exes.contains(exe_name))
+        })
+        .map(|(tool_name, _)| tool_name.clone())
+        .collect()
+}
+
+/// Remove all kept versions of the tool
+fn remove_versions(store_directory: &Path, tool_name: &str, dry_run: bool) {
+    let versions_directory = tool_versions_directory(store_directory, tool_name);
+
+    if !versions_directory.is_dir() {
+        return;
+    }
+
+    report_removal(&versions_directory, dry_run);
+
+    if !dry_run {
+        if let Err(e) = fs::remove_dir_all(&versions_directory) {
+            err::abort_with(&format!(
+                "Error removing {}: {}",
+                versions_directory.display(),
+                e
+            ));
+        }
+    }
+}
+
+fn remove_if_exists(path: &PathBuf) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+fn report_removal(exe_path: &Path, dry_run: bool) {
+    if dry_run {
+        println!("Would remove {}", exe_path.display());
+    } else {
+        println!("Removing {}", exe_path.display());
+    }
+}
+
+fn report_forgotten(tool_name: &str, dry_run: bool) {
+    if dry_run {
+        println!(
+            "Would forget '{}': its executables are already removed",
+            tool_name
+        );
+    } else {
+        println!(
+            "Forgetting '{}': its executables are already removed",
+            tool_name
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempdir::TempDir;
+
+    use crate::config::schema::ConfigAsset;
+    use crate::sync::state::{InstallRecord, InstalledExe};
+
+    fn record(exe_names: &[&str]) -> InstallRecord {
+        InstallRecord {
+            owner: "owner".to_string(),
+            repo: "repo".to_string(),
+            tag_name: "1.0.0".to_string(),
+            asset_name: "asset.tar.gz".to_string(),
+            asset_size: 42,
+            installed_at: 1_660_000_000,
+            extra_files: Vec::new(),
+            executables: exe_names
+                .iter()
+                .map(|exe_name| InstalledExe {
+                    name: exe_name.to_string(),
+                    sha256: "abcdef".to_string(),
+                })
+                .collect(),
+            previous_versions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn changed_executables_are_not_removable() {
+        let store_directory = TempDir::new("tool-sync-test").unwrap();
+        let store_path = store_directory.path();
+
+        let mut record = record(&["kubectx", "kubens", "kubectl"]);
+
+        for executable in record.executables.iter_mut() {
+            let path = store_path.join(&executable.name);
+            fs::write(&path, &executable.name).unwrap();
+            executable.sha256 = sha256_file(&path).unwrap();
+        }
+
+        // replaced by another installer and removed manually
+        fs::write(store_path.join("kubens"), "other").unwrap();
+        fs::remove_file(store_path.join("kubectl")).unwrap();
+
+        assert_eq!(
+            removable_files(store_path, &record),
+            vec![store_path.join("kubectx")]
+        );
+    }
+
+    #[test]
+    fn only_unconfigured_tools_are_stale() {
+        let config = Config {
+            store_directory: "pancake".to_string(),
+            tools: BTreeMap::from([
+                ("ripgrep".to_string(), ConfigAsset::default()),
+                ("my-rg".to_string(), ConfigAsset::default()),
+            ]),
+            ..Config::default()
+        };
+
+        let state = InstallState {
+            tools: BTreeMap::from([
+                ("ripgrep".to_string(), record(&["rg"])),
+                ("bat".to_string(), record(&["bat"])),
+                ("old-rg".to_string(), record(&["rg"])),
+            ]),
+        };
+
+        assert_eq!(stale_tools(&config, &state), vec!["bat".to_string()]);
+    }
+
+    #[test]
+    fn tool_sharing_any_executable_is_not_stale() {
+        let config = Config {
+            store_directory: "pancake".to_string(),
+            tools: BTreeMap::from([("kubens".to_string(), ConfigAsset::default())]),
+            ..Config::default()
+        };
+
+        let state = InstallState {
+            tools: BTreeMap::from([
+                ("kubens".to_string(), record(&["kubens"])),
+                ("kubectx".to_string(), record(&["kubectx", "kubens"])),
+                ("fzf".to_string(), record(&["fzf", "fzf-tmux"])),
+            ]),
+        };
+
+        assert_eq!(stale_tools(&config, &state), vec!["fzf".to_string()]);
+    }
+}
@@ -0,0 +1,242 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(target_family = "unix")]
+use std::os::unix::fs::PermissionsExt;
+
+/// New executable copied next to its final location, ready to replace it
+pub struct StagedFile {
+    tmp_path: PathBuf,
+    install_path: PathBuf,
+}
+
+/// Replaced executable with the path to its backup, if it existed before
+struct Replaced {
+    install_path: PathBuf,
+    backup_path: Option<PathBuf>,
+}
+
+/// Copy the file to a temporary file in the same directory as
+/// 'install_path', make it executable and flush it to disk. The target is
+/// not touched until 'replace_all'.
+fn stage(path: &Path, install_path: PathBuf) -> io::Result<StagedFile> {
+    let tmp_path = sibling_path(&install_path, ".", ".tool-sync.tmp");
+
+    let result = copy_synced(path, &tmp_path);
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result?;
+
+    Ok(StagedFile {
+        tmp_path,
+        install_path,
+    })
+}
+
+/// Stage all files given as pairs of the source path and the install path.
+/// If any of them fails, already staged files are removed.
+pub fn stage_all(files: Vec<(PathBuf, PathBuf)>) -> io::Result<Vec<StagedFile>> {
+    let mut staged = Vec::new();
+
+    for (path, install_path) in files {
+        match stage(&path, install_path) {
+            Ok(file) => staged.push(file),
+            Err(e) => {
+                discard(&staged);
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(staged)
+}
+
+/// Move all staged files to their final locations. Previous versions are kept
+/// as '<exe>.old' until all files are moved, so if anything fails, all
+/// executables are restored. Returns paths to the installed files.
+pub fn replace_all(staged: Vec<StagedFile>) -> io::Result<Vec<PathBuf>> {
+    let mut replaced: Vec<Replaced> = Vec::new();
+    let mut result = Ok(());
+
+    for file in staged.iter() {
+        match replace(file) {
+            Ok(done) => replaced.push(done),
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+
+    if let Err(e) = result {
+        discard(&staged);
+        rollback(&replaced);
+
+        return Err(e);
+    }
+
+    for done in replaced.iter() {
+        if let Some(backup_path) = &done.backup_path {
+            // a running executable can't be removed on Windows, so the backup
+            // stays there until the next install
+            let _ = fs::remove_file(backup_path);
+        }
+    }
+
+    Ok(staged.into_iter().map(|file| file.install_path).collect())
+}
+
+/// Keep the previous executable as '<exe>.old' and move the staged file over
+/// it. The executable at 'install_path' is never missing: the backup is a hard
+/// link (or a copy) and the staged file replaces the target in one rename.
+fn replace(file: &StagedFile) -> io::Result<Replaced> {
+    let backup_path = if file.install_path.exists() {
+        let backup_path = sibling_path(&file.install_path, "", ".old");
+        remove_if_exists(&backup_path)?;
+        link_or_copy(&file.install_path, &backup_path)?;
+        Some(backup_path)
+    } else {
+        None
+    };
+
+    if let Err(e) = fs::rename(&file.tmp_path, &file.install_path) {
+        if let Some(backup_path) = &backup_path {
+            let _ = fs::rename(backup_path, &file.install_path);
+        }
+
+        return Err(e);
+    }
+
+    Ok(Replaced {
+        install_path: file.install_path.clone(),
+        backup_path,
+    })
+}
+
+/// Remove temporary files of staged files that weren't moved
+fn discard(staged: &[StagedFile]) {
+    for file in staged.iter() {
+        let _ = fs::remove_file(&file.tmp_path);
+    }
+}
+
+/// Restore the previous versions of already replaced executables
+fn rollback(replaced: &[Replaced]) {
+    for done in replaced.iter().rev() {
+        match &done.backup_path {
+            Some(backup_path) => {
+                let _ = fs::rename(backup_path, &done.install_path);
+            }
+            None => {
+                let _ = fs::remove_file(&done.install_path);
+            }
+        }
+    }
+}
+
+fn link_or_copy(path: &Path, backup_path: &Path) -> io::Result<()> {
+    match fs::hard_link(path, backup_path) {
+        Ok(()) => Ok(()),
+        Err(_) => fs::copy(path, backup_path).map(|_| ()),
+    }
+}
+
+fn copy_synced(path: &Path, tmp_path: &Path) -> io::Result<()> {
+    fs::copy(path, tmp_path)?;
+    set_executable_permissions(tmp_path)?;
+    File::open(tmp_path)?.sync_all()
+}
+
+/// Path in the same directory as 'path' with the file name wrapped into the
+/// given prefix and suffix
+fn sibling_path(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+    let mut file_name = prefix.to_owned();
+    // unwrap is safe here because install paths always end with the exe name
+    file_name.push_str(&path.file_name().unwrap().to_string_lossy());
+    file_name.push_str(suffix);
+
+    path.with_file_name(file_name)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(target_family = "windows")]
+fn set_executable_permissions(_exe_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(target_family = "unix")]
+fn set_executable_permissions(exe_path: &Path) -> io::Result<()> {
+    fs::set_permissions(exe_path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn dir_entries(dir: &Path) -> Vec<String> {
+        let mut entries: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn executables_are_replaced() {
+        let src_dir = TempDir::new("tool-sync-test").unwrap();
+        let store_dir = TempDir::new("tool-sync-test").unwrap();
+
+        let new_exe = src_dir.path().join("tool");
+        fs::write(&new_exe, "new").unwrap();
+
+        let install_path = store_dir.path().join("tool");
+        fs::write(&install_path, "old").unwrap();
+
+        let staged = stage(&new_exe, install_path.clone()).unwrap();
+
+        // the target is untouched until the replacement
+        assert_eq!(fs::read_to_string(&install_path).unwrap(), "old");
+
+        assert_eq!(
+            replace_all(vec![staged]).unwrap(),
+            vec![install_path.clone()]
+        );
+        assert_eq!(fs::read_to_string(&install_path).unwrap(), "new");
+        assert_eq!(dir_entries(store_dir.path()), vec!["tool"]);
+    }
+
+    #[test]
+    fn failed_replacement_restores_previous_executables() {
+        let src_dir = TempDir::new("tool-sync-test").unwrap();
+        let store_dir = TempDir::new("tool-sync-test").unwrap();
+
+        let new_exe = src_dir.path().join("tool");
+        fs::write(&new_exe, "new").unwrap();
+
+        let first = store_dir.path().join("first");
+        fs::write(&first, "old").unwrap();
+        let second = store_dir.path().join("second");
+
+        let staged = vec![
+            stage(&new_exe, first.clone()).unwrap(),
+            stage(&new_exe, second.clone()).unwrap(),
+        ];
+
+        // simulate a failure in the middle of the replacement
+        fs::remove_file(&staged[1].tmp_path).unwrap();
+
+        assert!(replace_all(staged).is_err());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "old");
+        assert_eq!(dir_entries(store_dir.path()), vec!["first"]);
+    }
+}
@@ -0,0 +1,317 @@
+use console::style;
+use indicatif::HumanBytes;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::config::cli::{Output, OutputFormat};
+use crate::config::schema::Config;
+
+use super::outcome::ErrorKind;
+use super::progress::SyncProgress;
+
+/// Something that happened while syncing a single tool
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// Work on the tool started
+    Started { tag: &'a str },
+
+    /// Fetching info about the release
+    FetchingRelease,
+
+    /// The release and the asset matching the config are found
+    ReleaseResolved {
+        tag_name: &'a str,
+        asset_name: &'a str,
+    },
+
+    /// Downloading of the asset started
+    DownloadStarted {
+        asset_name: &'a str,
+        size: Option<u64>,
+    },
+
+    /// Number of downloaded bytes of the asset
+    DownloadProgress { downloaded: u64, size: Option<u64> },
+
+    /// The asset is fully downloaded
+    DownloadFinished { asset_name: &'a str },
+
+    /// The checksum asset of the release doesn't list the downloaded asset,
+    /// so it wasn't verified against it
+    ChecksumSkipped { checksum_asset: &'a str },
+
+    /// The tool was installed from the release with the given tag
+    Installed {
+        tag_name: &'a str,
+        executables: Vec<InstalledFile<'a>>,
+    },
+
+    /// The tool from the release with the given tag is already installed
+    UpToDate { tag_name: &'a str },
+
+    /// The tool wasn't installed for the given reason
+    Skipped { tag: &'a str, reason: &'a str },
+
+    /// The tool failed with the given error
+    Failed {
+        tag: &'a str,
+        kind: ErrorKind,
+        message: &'a str,
+    },
+
+    /// The asset of the tool is pinned in the lock file
+    Locked { tag_name: &'a str, sha256: &'a str },
+
+    /// The installed tool is compared with the available release
+    Checked { status: &'a str },
+}
+
+/// A single executable installed into 'store_directory'
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct InstalledFile<'a> {
+    pub path: &'a Path,
+    pub sha256: &'a str,
+}
+
+/// Receives progress of all tools. Install, lock and status share the same
+/// code and only the reporter decides how to render it.
+pub trait Reporter: Sync {
+    fn report(&self, tool_name: &str, event: Event);
+}
+
+/// Progress bars for humans, plain log lines when stdout isn't a terminal, or
+/// JSON events for scripts, depending on the output format. Tools and their
+/// tags are known upfront to align lines.
+pub fn mk_reporter(output: Output, tools: Vec<String>, tags: Vec<String>) -> Box<dyn Reporter> {
+    if output.quiet {
+        return Box::new(SilentReporter);
+    }
+
+    match output.format {
+        OutputFormat::Human if console::user_attended() => Box::new(SyncProgress::new(tools, tags)),
+        OutputFormat::Human => Box::new(LogReporter::new()),
+        OutputFormat::Json => Box::new(JsonReporter),
+    }
+}
+
+/// Reporter for all tools from the config
+pub fn config_reporter(output: Output, config: &Config) -> Box<dyn Reporter> {
+    let tools: Vec<String> = config.tools.keys().cloned().collect();
+    let tags: Vec<String> = config
+        .tools
+        .values()
+        .map(|config_asset| config_asset.tag_label())
+        .collect();
+
+    mk_reporter(output, tools, tags)
+}
+
+/// Prints each event as a single line of JSON to stdout
+pub struct JsonReporter;
+
+/// Event together with the name of its tool
+#[derive(Serialize)]
+struct ToolEvent<'a> {
+    tool: &'a str,
+
+    #[serde(flatten)]
+    event: Event<'a>,
+}
+
+impl JsonReporter {
+    fn encode(tool_name: &str, event: Event) -> String {
+        // serializing a struct of strings and numbers never fails
+        serde_json::to_string(&ToolEvent {
+            tool: tool_name,
+            event,
+        })
+        .unwrap()
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn report(&self, tool_name: &str, event: Event) {
+        println!("{}", JsonReporter::encode(tool_name, event));
+    }
+}
+
+/// Prints notable events as plain lines of text, e.g. for CI logs where
+/// progress bars can't be redrawn:
+///
+/// [ripgrep 13.0.0] downloading 1.90 MiB
+pub struct LogReporter {
+    /// Tag of each tool, replaced by the release tag once it's resolved
+    tags: Mutex<HashMap<String, String>>,
+}
+
+impl LogReporter {
+    pub fn new() -> LogReporter {
+        LogReporter {
+            tags: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The line to print for the event, if it's worth printing
+    fn line(&self, tool_name: &str, event: Event) -> Option<String> {
+        // the lock is poisoned only when another installation panicked
+        let mut tags = self.tags.lock().unwrap();
+
+        match &event {
+            Event::Started { tag: tag_name }
+            | Event::ReleaseResolved { tag_name, .. }
+            | Event::Installed { tag_name, .. }
+            | Event::UpToDate { tag_name }
+            | Event::Locked { tag_name, .. } => {
+                tags.insert(tool_name.to_owned(), tag_name.to_string());
+            }
+            _ => {}
+        }
+
+        let msg = match event {
+            Event::FetchingRelease => "fetching release info".to_owned(),
+            Event::ReleaseResolved { asset_name, .. } => format!("found asset {}", asset_name),
+            Event::DownloadStarted { size: None, .. } => "downloading".to_owned(),
+            Event::DownloadStarted {
+                size: Some(size), ..
+            } => format!("downloading {}", HumanBytes(size)),
+            Event::Started { .. } | Event::DownloadProgress { .. } | Event::Checked { .. } => {
+                return None
+            }
+            Event::DownloadFinished { .. } => "downloaded".to_owned(),
+            Event::ChecksumSkipped { checksum_asset } => format!(
+                "{}: {} doesn't list the asset",
+                style("checksum not verified").yellow(),
+                checksum_asset
+            ),
+            Event::Installed { .. } => format!("{}", style("installed").green()),
+            Event::UpToDate { .. } => "up to date".to_owned(),
+            Event::Skipped { reason, .. } => format!("{}: {}", style("skipped").yellow(), reason),
+            Event::Failed { message, .. } => format!("{}: {}", style("failed").red(), message),
+            Event::Locked { .. } => format!("{}", style("locked").green()),
+        };
+
+        let tag = tags.get(tool_name).map_or("latest", |tag| tag.as_str());
+        Some(format!("[{} {}] {}", tool_name, tag, msg))
+    }
+}
+
+impl Reporter for LogReporter {
+    fn report(&self, tool_name: &str, event: Event) {
+        if let Some(line) = self.line(tool_name, event) {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Reporter that ignores all events
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {
+    fn report(&self, _tool_name: &str, _event: Event) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_events() {
+        assert_eq!(
+            JsonReporter::encode(
+                "ripgrep",
+                Event::ReleaseResolved {
+                    tag_name: "13.0.0",
+                    asset_name: "ripgrep.tar.gz"
+                }
+            ),
+            r#"{"tool":"ripgrep","event":"release_resolved","tag_name":"13.0.0","asset_name":"ripgrep.tar.gz"}"#
+        );
+
+        assert_eq!(
+            JsonReporter::encode("ripgrep", Event::FetchingRelease),
+            r#"{"tool":"ripgrep","event":"fetching_release"}"#
+        );
+
+        assert_eq!(
+            JsonReporter::encode(
+                "ripgrep",
+                Event::Failed {
+                    tag: "latest",
+                    kind: ErrorKind::Network,
+                    message: "timeout"
+                }
+            ),
+            r#"{"tool":"ripgrep","event":"failed","tag":"latest","kind":"network","message":"timeout"}"#
+        );
+    }
+
+    #[test]
+    fn log_lines() {
+        console::set_colors_enabled(false);
+        let reporter = LogReporter::new();
+
+        assert_eq!(
+            reporter.line("ripgrep", Event::Started { tag: "latest" }),
+            None
+        );
+        assert_eq!(
+            reporter.line("ripgrep", Event::FetchingRelease),
+            Some("[ripgrep latest] fetching release info".to_owned())
+        );
+        assert_eq!(
+            reporter.line(
+                "ripgrep",
+                Event::ReleaseResolved {
+                    tag_name: "13.0.0",
+                    asset_name: "ripgrep.tar.gz"
+                }
+            ),
+            Some("[ripgrep 13.0.0] found asset ripgrep.tar.gz".to_owned())
+        );
+        assert_eq!(
+            reporter.line(
+                "ripgrep",
+                Event::DownloadStarted {
+                    asset_name: "ripgrep.tar.gz",
+                    size: Some(2 * 1024 * 1024)
+                }
+            ),
+            Some("[ripgrep 13.0.0] downloading 2.00 MiB".to_owned())
+        );
+        assert_eq!(
+            reporter.line(
+                "ripgrep",
+                Event::DownloadProgress {
+                    downloaded: 1024,
+                    size: Some(2 * 1024 * 1024)
+                }
+            ),
+            None
+        );
+        assert_eq!(
+            reporter.line(
+                "ripgrep",
+                Event::ChecksumSkipped {
+                    checksum_asset: "checksums.sha256"
+                }
+            ),
+            Some(
+                "[ripgrep 13.0.0] checksum not verified: checksums.sha256 doesn't list the asset"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            reporter.line(
+                "ripgrep",
+                Event::Installed {
+                    tag_name: "13.0.0",
+                    executables: Vec::new()
+                }
+            ),
+            Some("[ripgrep 13.0.0] installed".to_owned())
+        );
+    }
+}
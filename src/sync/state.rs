@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::err;
+
+/// Name of the file inside 'store_directory' that records all installed tools
+pub const STATE_FILE_NAME: &str = ".tool-sync-state.toml";
+
+/// Everything 'tool-sync' knows about tools it installed into 'store_directory'
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct InstallState {
+    /// Install records for each tool, keyed by tool name from the config
+    #[serde(default)]
+    pub tools: BTreeMap<String, InstallRecord>,
+}
+
+/// Details about a single installed tool
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InstallRecord {
+    /// GitHub repository author
+    pub owner: String,
+
+    /// GitHub repository name
+    pub repo: String,
+
+    /// Resolved release tag the tool was installed from
+    pub tag_name: String,
+
+    /// Full name of the downloaded asset
+    pub asset_name: String,
+
+    /// Size of the downloaded asset in bytes
+    pub asset_size: u64,
+
+    /// Time of the installation in seconds since the Unix epoch
+    pub installed_at: u64,
+
+    /// Completions and man pages installed outside of 'store_directory'
+    #[serde(default)]
+    pub extra_files: Vec<PathBuf>,
+
+    /// All executables installed from the asset
+    pub executables: Vec<InstalledExe>,
+
+    /// Earlier versions kept in the versions directory for rollback, the most
+    /// recently active version last. Skipped when empty, as TOML can't have
+    /// an empty array after the 'executables' tables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous_versions: Vec<InstalledVersion>,
+}
+
+/// A version of the tool kept in the versions directory
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InstalledVersion {
+    /// Resolved release tag the version was installed from
+    pub tag_name: String,
+
+    /// Full name of the downloaded asset
+    pub asset_name: String,
+
+    /// Size of the downloaded asset in bytes
+    pub asset_size: u64,
+
+    /// Time of the installation in seconds since the Unix epoch
+    pub installed_at: u64,
+
+    /// All executables installed from the asset
+    pub executables: Vec<InstalledExe>,
+}
+
+/// A single executable installed from the asset
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InstalledExe {
+    /// Name of the installed file inside 'store_directory'
+    pub name: String,
+
+    /// sha256 of the installed file
+    pub sha256: String,
+}
+
+#[derive(Debug)]
+pub enum StateError {
+    IO(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl StateError {
+    pub fn display(&self) -> String {
+        match self {
+            StateError::IO(e) => format!("[IO Error] {}", e),
+            StateError::Parse(e) => format!("[Parsing Error] {}", e),
+            StateError::Serialize(e) => format!("[Serialize Error] {}", e),
+        }
+    }
+}
+
+impl InstallState {
+    /// Read the state file from 'store_directory'. A missing file means that
+    /// nothing was installed yet.
+    pub fn load(store_directory: &Path) -> Result<InstallState, StateError> {
+        let state_path = state_path(store_directory);
+
+        match fs::read_to_string(&state_path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(InstallState::default()),
+            Err(e) => Err(StateError::IO(e)),
+            Ok(contents) => toml::from_str(&contents).map_err(StateError::Parse),
+        }
+    }
+
+    /// Write the state file to 'store_directory' atomically
+    pub fn save(&self, store_directory: &Path) -> Result<(), StateError> {
+        let contents = toml::to_string(self).map_err(StateError::Serialize)?;
+
+        write_atomically(&state_path(store_directory), &contents).map_err(StateError::IO)
+    }
+
+    /// Check whether the given asset from the release with the given tag is
+    /// already installed and its executable is still in 'store_directory'
+    pub fn is_up_to_date(
+        &self,
+        store_directory: &Path,
+        tool_name: &str,
+        tag_name: &str,
+        asset_name: &str,
+    ) -> bool {
+        match self.tools.get(tool_name) {
+            None => false,
+            Some(record) => {
+                record.tag_name == tag_name
+                    && record.asset_name == asset_name
+                    && record.is_installed(store_directory)
+            }
+        }
+    }
+}
+
+impl InstallRecord {
+    /// Names of all installed files inside 'store_directory'
+    pub fn exe_names(&self) -> Vec<&str> {
+        self.executables
+            .iter()
+            .map(|executable| executable.name.as_str())
+            .collect()
+    }
+
+    /// Check whether all executables of the tool are still in 'store_directory'
+    pub fn is_installed(&self, store_directory: &Path) -> bool {
+        self.executables
+            .iter()
+            .all(|executable| store_directory.join(&executable.name).is_file())
+    }
+
+    /// The currently active version of the tool
+    pub fn current_version(&self) -> InstalledVersion {
+        InstalledVersion {
+            tag_name: self.tag_name.clone(),
+            asset_name: self.asset_name.clone(),
+            asset_size: self.asset_size,
+            installed_at: self.installed_at,
+            executables: self.executables.clone(),
+        }
+    }
+
+    /// Make the given version active, keeping the current one as the most
+    /// recent previous version
+    pub fn switch_to(&mut self, version: InstalledVersion) {
+        let current = self.current_version();
+
+        self.tag_name = version.tag_name;
+        self.asset_name = version.asset_name;
+        self.asset_size = version.asset_size;
+        self.installed_at = version.installed_at;
+        self.executables = version.executables;
+
+        self.previous_versions.push(current);
+    }
+}
+
+/// Read the state file from 'store_directory' and exit with error if it can't
+/// be read
+pub fn load_state(store_directory: &Path) -> InstallState {
+    match InstallState::load(store_directory) {
+        Err(e) => {
+            err::abort_with(&format!(
+                "Error reading install state in {}: {}",
+                store_directory.display(),
+                e.display()
+            ));
+        }
+        Ok(state) => state,
+    }
+}
+
+/// Write the state file to 'store_directory' and exit with error if it can't
+/// be written
+pub fn save_state(state: &InstallState, store_directory: &Path) {
+    if let Err(e) = state.save(store_directory) {
+        err::abort_with(&format!(
+            "Error saving install state in {}: {}",
+            store_directory.display(),
+            e.display()
+        ));
+    }
+}
+
+fn state_path(store_directory: &Path) -> PathBuf {
+    store_directory.join(STATE_FILE_NAME)
+}
+
+/// Write a file next to the target and then rename it, so a crash never
+/// leaves a half-written file behind
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Current time in seconds since the Unix epoch
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn ripgrep_record() -> InstallRecord {
+        InstallRecord {
+            owner: "BurntSushi".to_string(),
+            repo: "ripgrep".to_string(),
+            tag_name: "13.0.0".to_string(),
+            asset_name: "ripgrep-13.0.0-x86_64-unknown-linux-musl.tar.gz".to_string(),
+            asset_size: 1_942_365,
+            installed_at: 1_660_000_000,
+            extra_files: vec![PathBuf::from("/usr/local/share/man/man1/rg.1")],
+            executables: vec![InstalledExe {
+                name: "rg".to_string(),
+                sha256: "abcdef".to_string(),
+            }],
+            previous_versions: vec![InstalledVersion {
+                tag_name: "12.1.1".to_string(),
+                asset_name: "ripgrep-12.1.1-x86_64-unknown-linux-musl.tar.gz".to_string(),
+                asset_size: 1_500_000,
+                installed_at: 1_650_000_000,
+                executables: vec![InstalledExe {
+                    name: "rg".to_string(),
+                    sha256: "fedcba".to_string(),
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn missing_state_file_is_empty() {
+        let store_directory = TempDir::new("tool-sync-test").unwrap();
+        let state = InstallState::load(store_directory.path()).unwrap();
+
+        assert_eq!(state, InstallState::default());
+    }
+
+    #[test]
+    fn state_roundtrip() {
+        let store_directory = TempDir::new("tool-sync-test").unwrap();
+
+        let first_install = InstallRecord {
+            previous_versions: Vec::new(),
+            ..ripgrep_record()
+        };
+
+        let mut state = InstallState::default();
+        state.tools.insert("ripgrep".to_string(), ripgrep_record());
+        state.tools.insert("rg".to_string(), first_install);
+        state.save(store_directory.path()).unwrap();
+
+        let loaded = InstallState::load(store_directory.path()).unwrap();
+
+        assert_eq!(loaded, state);
+        assert!(!store_directory
+            .path()
+            .join(format!("{}.tmp", STATE_FILE_NAME))
+            .exists());
+    }
+
+    #[test]
+    fn switch_to_previous_version() {
+        let mut record = ripgrep_record();
+        let current = record.current_version();
+        let previous = record.previous_versions.pop().unwrap();
+
+        record.switch_to(previous.clone());
+
+        assert_eq!(record.current_version(), previous);
+        assert_eq!(record.previous_versions, vec![current]);
+    }
+
+    #[test]
+    fn up_to_date_requires_same_tag_asset_and_file() {
+        let store_directory = TempDir::new("tool-sync-test").unwrap();
+        let store_path = store_directory.path();
+
+        let record = ripgrep_record();
+        let mut state = InstallState::default();
+        state.tools.insert("ripgrep".to_string(), record.clone());
+
+        // executable was removed from 'store_directory'
+        assert!(!state.is_up_to_date(store_path, "ripgrep", &record.tag_name, &record.asset_name));
+
+        fs::write(store_path.join("rg"), "rg").unwrap();
+
+        assert!(state.is_up_to_date(store_path, "ripgrep", &record.tag_name, &record.asset_name));
+        assert!(!state.is_up_to_date(store_path, "ripgrep", "14.0.0", &record.asset_name));
+        assert!(!state.is_up_to_date(store_path, "ripgrep", &record.tag_name, "ripgrep.zip"));
+        assert!(!state.is_up_to_date(store_path, "bat", &record.tag_name, &record.asset_name));
+    }
+}
@@ -0,0 +1,296 @@
+use console::style;
+use serde::Serialize;
+use std::error::Error;
+use std::path::Path;
+use std::process;
+
+use crate::config::cli::{Output, OutputFormat};
+use crate::config::schema::{Config, ConfigAsset};
+use crate::model::tool::{Tool, ToolInfo};
+
+use super::configure::configure_tool;
+use super::download::Downloader;
+use super::no_tools_message;
+use super::provider::ApiConfig;
+use super::report::{config_reporter, Event, Reporter};
+use super::state::{load_state, InstallRecord, InstallState};
+
+/// How the installed tool compares to the available release
+#[derive(Debug, PartialEq, Eq)]
+enum ToolStatus {
+    UpToDate,
+    Outdated,
+    NotInstalled,
+    Error(String),
+}
+
+impl ToolStatus {
+    fn display(&self) -> String {
+        match self {
+            ToolStatus::UpToDate => "up to date".to_string(),
+            ToolStatus::Outdated => "update available".to_string(),
+            ToolStatus::NotInstalled => "not installed".to_string(),
+            ToolStatus::Error(e) => e.clone(),
+        }
+    }
+
+    /// Short name of the status for JSON reports
+    fn name(&self) -> &'static str {
+        match self {
+            ToolStatus::UpToDate => "up_to_date",
+            ToolStatus::Outdated => "outdated",
+            ToolStatus::NotInstalled => "not_installed",
+            ToolStatus::Error(_) => "error",
+        }
+    }
+}
+
+/// A single row of the status table
+struct StatusRow {
+    tool_name: String,
+    installed_tag: Option<String>,
+    available_tag: Option<String>,
+    status: ToolStatus,
+}
+
+/// A single row of the status table in the JSON report
+#[derive(Serialize)]
+struct StatusReport<'a> {
+    tool: &'a str,
+    installed_tag: Option<&'a str>,
+    available_tag: Option<&'a str>,
+    status: &'static str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+/// The whole JSON report of 'tool status'
+#[derive(Serialize)]
+struct StatusDocument<'a> {
+    tools: Vec<StatusReport<'a>>,
+}
+
+/// Compare installed tools with the available releases without installing
+/// anything. Exits with code 1 if any tool is not up to date.
+pub fn status(config: Config, output: Output) {
+    if config.tools.is_empty() {
+        no_tools_message();
+        return;
+    }
+
+    let store_directory = config.ensure_store_directory();
+
+    let state = load_state(&store_directory);
+
+    let reporter = config_reporter(output, &config);
+    let api_config = ApiConfig::from_config(&config);
+
+    let rows: Vec<StatusRow> = config
+        .tools
+        .iter()
+        .map(|(tool_name, config_asset)| {
+            let tag = config_asset.tag_label();
+            reporter.report(tool_name, Event::Started { tag: &tag });
+
+            let row = tool_status(
+                reporter.as_ref(),
+                &api_config,
+                &state,
+                &store_directory,
+                tool_name,
+                config_asset,
+            );
+
+            reporter.report(
+                tool_name,
+                Event::Checked {
+                    status: row.status.name(),
+                },
+            );
+
+            row
+        })
+        .collect();
+
+    match output.format {
+        OutputFormat::Human => print_table(&rows),
+        OutputFormat::Json => println!("{}", rows_to_json(&rows)),
+    }
+
+    if rows.iter().any(|row| row.status != ToolStatus::UpToDate) {
+        process::exit(1);
+    }
+}
+
+fn tool_status(
+    reporter: &dyn Reporter,
+    api_config: &ApiConfig,
+    state: &InstallState,
+    store_directory: &Path,
+    tool_name: &str,
+    config_asset: &ConfigAsset,
+) -> StatusRow {
+    let record: Option<&InstallRecord> = state.tools.get(tool_name);
+    let installed_tag = record.map(|record| record.tag_name.clone());
+
+    let tool_info = match configure_tool(tool_name, config_asset) {
+        Tool::Known(tool_info) => tool_info,
+        Tool::Error(e) => {
+            return StatusRow {
+                tool_name: tool_name.to_owned(),
+                installed_tag,
+                available_tag: None,
+                status: ToolStatus::Error(e.display()),
+            }
+        }
+    };
+
+    let available = fetch_available(reporter, api_config, tool_name, &tool_info);
+
+    match available {
+        Err(e) => StatusRow {
+            tool_name: tool_name.to_owned(),
+            installed_tag,
+            available_tag: None,
+            status: ToolStatus::Error(format!("[error] {}", e)),
+        },
+        Ok((available_tag, asset_name)) => {
+            let status = match record {
+                None => ToolStatus::NotInstalled,
+                Some(record) => {
+                    if state.is_up_to_date(store_directory, tool_name, &available_tag, &asset_name)
+                    {
+                        ToolStatus::UpToDate
+                    } else if !record.is_installed(store_directory) {
+                        ToolStatus::NotInstalled
+                    } else {
+                        ToolStatus::Outdated
+                    }
+                }
+            };
+
+            StatusRow {
+                tool_name: tool_name.to_owned(),
+                installed_tag,
+                available_tag: Some(available_tag),
+                status,
+            }
+        }
+    }
+}
+
+/// Fetch the release info and return its tag and the name of the matching asset
+fn fetch_available(
+    reporter: &dyn Reporter,
+    api_config: &ApiConfig,
+    tool_name: &str,
+    tool_info: &ToolInfo,
+) -> Result<(String, String), Box<dyn Error>> {
+    match tool_info.asset_name.get_name_by_os() {
+        None => {
+            Err("Don't know the asset name for this OS: specify it explicitly in the config".into())
+        }
+        Some(asset_name) => {
+            let provider = api_config.provider(tool_info);
+
+            let downloader = Downloader {
+                provider: &provider,
+                owner: &tool_info.owner,
+                repo: &tool_info.repo,
+                tag: &tool_info.tag,
+                prerelease: tool_info.prerelease,
+                tag_pattern: tool_info.tag_pattern.as_deref(),
+                tool_name,
+                reporter,
+                asset_name,
+            };
+
+            let release = downloader.download_release()?;
+            let asset = downloader.find_asset(&release)?;
+
+            Ok((release.tag_name.clone(), asset.name.clone()))
+        }
+    }
+}
+
+/// All rows of the status table as a single JSON document
+fn rows_to_json(rows: &[StatusRow]) -> String {
+    let tools: Vec<StatusReport> = rows
+        .iter()
+        .map(|row| StatusReport {
+            tool: &row.tool_name,
+            installed_tag: row.installed_tag.as_deref(),
+            available_tag: row.available_tag.as_deref(),
+            status: row.status.name(),
+            error: match &row.status {
+                ToolStatus::Error(e) => Some(e),
+                _ => None,
+            },
+        })
+        .collect();
+
+    // serializing a struct of strings never fails
+    serde_json::to_string(&StatusDocument { tools }).unwrap()
+}
+
+fn print_table(rows: &[StatusRow]) {
+    let headers = ["Tool", "Installed", "Available", "Status"];
+
+    let cells: Vec<[String; 3]> = rows
+        .iter()
+        .map(|row| {
+            [
+                row.tool_name.clone(),
+                row.installed_tag.clone().unwrap_or_else(|| "-".into()),
+                row.available_tag.clone().unwrap_or_else(|| "-".into()),
+            ]
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..3)
+        .map(|i| {
+            cells
+                .iter()
+                .map(|row| row[i].len())
+                .chain([headers[i].len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    println!(
+        "{}",
+        style(format!(
+            "{:w0$}  {:w1$}  {:w2$}  {}",
+            headers[0],
+            headers[1],
+            headers[2],
+            headers[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+        ))
+        .bold()
+    );
+
+    for (row, cell) in rows.iter().zip(cells.iter()) {
+        let status = match row.status {
+            ToolStatus::UpToDate => style(row.status.display()).green(),
+            ToolStatus::Outdated => style(row.status.display()).yellow(),
+            ToolStatus::NotInstalled => style(row.status.display()).yellow(),
+            ToolStatus::Error(_) => style(row.status.display()).red(),
+        };
+
+        println!(
+            "{:w0$}  {:w1$}  {:w2$}  {}",
+            cell[0],
+            cell[1],
+            cell[2],
+            status,
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+        );
+    }
+}
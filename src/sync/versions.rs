@@ -0,0 +1,256 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::schema::Config;
+use crate::err;
+
+use super::replace;
+use super::state::{load_state, save_state, InstallRecord, InstalledVersion};
+
+/// Directory inside 'store_directory' with all kept versions of tools
+pub const VERSIONS_DIRECTORY: &str = ".versions";
+
+/// Switch the tool to the previously installed version without downloading
+/// anything. Shell completions and man pages aren't versioned, so the ones of
+/// the current version stay installed.
+pub fn rollback(config: Config, tool_name: &str) {
+    let store_directory = config.ensure_store_directory();
+    let mut state = load_state(&store_directory);
+
+    let record = match state.tools.get_mut(tool_name) {
+        Some(record) => record,
+        None => err::abort_with(&format!(
+            "Tool '{}' wasn't installed by 'tool-sync'",
+            tool_name
+        )),
+    };
+
+    let previous = match record.previous_versions.pop() {
+        Some(previous) => previous,
+        None => err::abort_with(&format!(
+            "No previous version of '{}' to roll back to",
+            tool_name
+        )),
+    };
+
+    let version_directory = version_directory(&store_directory, tool_name, &previous.tag_name);
+
+    if !has_files(&store_directory, tool_name, &previous) {
+        err::abort_with(&format!(
+            "Files of '{}' {} are missing in {}",
+            tool_name,
+            previous.tag_name,
+            version_directory.display()
+        ));
+    }
+
+    let files: Vec<PathBuf> = previous
+        .executables
+        .iter()
+        .map(|executable| version_directory.join(&executable.name))
+        .collect();
+
+    if let Err(e) = activate(&store_directory, &files) {
+        err::abort_with(&format!(
+            "Error switching '{}' to {}: {}",
+            tool_name, previous.tag_name, e
+        ));
+    }
+
+    // executables that exist only in the current version are removed
+    for executable in record.executables.iter() {
+        if !previous
+            .executables
+            .iter()
+            .any(|previous_exe| previous_exe.name == executable.name)
+        {
+            let _ = fs::remove_file(store_directory.join(&executable.name));
+        }
+    }
+
+    println!(
+        "Rolled back '{}' from {} to {}",
+        tool_name, record.tag_name, previous.tag_name
+    );
+
+    record.switch_to(previous);
+    save_state(&state, &store_directory);
+}
+
+/// Directory with all kept versions of a single tool
+pub fn tool_versions_directory(store_directory: &Path, tool_name: &str) -> PathBuf {
+    store_directory.join(VERSIONS_DIRECTORY).join(tool_name)
+}
+
+/// Directory with executables of the tool installed from the given tag
+pub fn version_directory(store_directory: &Path, tool_name: &str, tag_name: &str) -> PathBuf {
+    tool_versions_directory(store_directory, tool_name).join(tag_name)
+}
+
+/// Copy executables from a version directory to 'store_directory' under the
+/// same names and return paths to the installed files
+pub fn activate(store_directory: &Path, files: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let files = files
+        .iter()
+        .map(|path| {
+            // unwrap is safe here because version files always end with the exe name
+            let install_path = store_directory.join(path.file_name().unwrap());
+            (path.clone(), install_path)
+        })
+        .collect();
+
+    let staged = replace::stage_all(files)?;
+    replace::replace_all(staged)
+}
+
+/// Previous versions of the tool after installing 'tag_name'. The replaced
+/// version becomes the most recent previous one, and the oldest versions are
+/// dropped together with their files, so that at most 'keep_versions'
+/// versions are kept, including the active one.
+pub fn previous_versions(
+    store_directory: &Path,
+    tool_name: &str,
+    old_record: Option<&InstallRecord>,
+    tag_name: &str,
+    keep_versions: usize,
+) -> Vec<InstalledVersion> {
+    let mut versions = Vec::new();
+
+    if let Some(old_record) = old_record {
+        versions.extend(old_record.previous_versions.iter().cloned());
+
+        // tools installed before versions were kept have nothing to roll back to
+        let current = old_record.current_version();
+        if has_files(store_directory, tool_name, &current) {
+            versions.push(current);
+        }
+    }
+
+    // the directory of the installed tag now holds the new files
+    versions.retain(|version| version.tag_name != tag_name);
+
+    let excess = versions.len().saturating_sub(keep_versions - 1);
+
+    for version in versions.drain(..excess) {
+        // failing to clean up an old version shouldn't fail the installation
+        let _ = fs::remove_dir_all(version_directory(
+            store_directory,
+            tool_name,
+            &version.tag_name,
+        ));
+    }
+
+    versions
+}
+
+/// Check whether all executables of the version are in its directory
+fn has_files(store_directory: &Path, tool_name: &str, version: &InstalledVersion) -> bool {
+    let version_directory = version_directory(store_directory, tool_name, &version.tag_name);
+
+    version
+        .executables
+        .iter()
+        .all(|executable| version_directory.join(&executable.name).is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    use crate::sync::state::InstalledExe;
+
+    fn version(tag_name: &str) -> InstalledVersion {
+        InstalledVersion {
+            tag_name: tag_name.to_string(),
+            asset_name: "rg.tar.gz".to_string(),
+            asset_size: 42,
+            installed_at: 1_660_000_000,
+            executables: vec![InstalledExe {
+                name: "rg".to_string(),
+                sha256: "abcdef".to_string(),
+            }],
+        }
+    }
+
+    fn record(current: &str, previous: &[&str]) -> InstallRecord {
+        let current = version(current);
+
+        InstallRecord {
+            owner: "BurntSushi".to_string(),
+            repo: "ripgrep".to_string(),
+            tag_name: current.tag_name,
+            asset_name: current.asset_name,
+            asset_size: current.asset_size,
+            installed_at: current.installed_at,
+            extra_files: Vec::new(),
+            executables: current.executables,
+            previous_versions: previous.iter().map(|tag_name| version(tag_name)).collect(),
+        }
+    }
+
+    fn write_version(store_directory: &Path, tag_name: &str) {
+        let version_directory = version_directory(store_directory, "ripgrep", tag_name);
+        fs::create_dir_all(&version_directory).unwrap();
+        fs::write(version_directory.join("rg"), tag_name).unwrap();
+    }
+
+    fn tags(versions: &[InstalledVersion]) -> Vec<&str> {
+        versions
+            .iter()
+            .map(|version| version.tag_name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn replaced_version_is_kept() {
+        let store_directory = TempDir::new("tool-sync-test").unwrap();
+        let store_path = store_directory.path();
+
+        write_version(store_path, "12.0.0");
+        write_version(store_path, "13.0.0");
+
+        let old_record = record("13.0.0", &["12.0.0"]);
+        let versions = previous_versions(store_path, "ripgrep", Some(&old_record), "14.0.0", 3);
+
+        assert_eq!(tags(&versions), vec!["12.0.0", "13.0.0"]);
+    }
+
+    #[test]
+    fn oldest_versions_are_removed() {
+        let store_directory = TempDir::new("tool-sync-test").unwrap();
+        let store_path = store_directory.path();
+
+        write_version(store_path, "12.0.0");
+        write_version(store_path, "13.0.0");
+
+        let old_record = record("13.0.0", &["12.0.0"]);
+        let versions = previous_versions(store_path, "ripgrep", Some(&old_record), "14.0.0", 2);
+
+        assert_eq!(tags(&versions), vec!["13.0.0"]);
+        assert!(!version_directory(store_path, "ripgrep", "12.0.0").exists());
+        assert!(version_directory(store_path, "ripgrep", "13.0.0").exists());
+
+        let versions = previous_versions(store_path, "ripgrep", Some(&old_record), "14.0.0", 1);
+        assert!(versions.is_empty());
+    }
+
+    #[test]
+    fn reinstalled_and_missing_versions_are_not_kept() {
+        let store_directory = TempDir::new("tool-sync-test").unwrap();
+        let store_path = store_directory.path();
+
+        write_version(store_path, "12.0.0");
+
+        // 13.0.0 was installed before versions were kept
+        let old_record = record("13.0.0", &["12.0.0"]);
+        let versions = previous_versions(store_path, "ripgrep", Some(&old_record), "14.0.0", 3);
+        assert_eq!(tags(&versions), vec!["12.0.0"]);
+
+        // going back to 12.0.0 doesn't keep it as a previous version
+        let versions = previous_versions(store_path, "ripgrep", Some(&old_record), "12.0.0", 3);
+        assert!(versions.is_empty());
+        assert!(version_directory(store_path, "ripgrep", "12.0.0").exists());
+    }
+}